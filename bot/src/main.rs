@@ -1,11 +1,18 @@
 mod utils;
 
 use chrono::Local;
+use singbox::clash::ClashConfig;
 use singbox::config;
+use singbox::config::platform::{Platform, PLATFORMS};
+use singbox::config::snippet::OutboundSnippets;
+use singbox::config::OutputFormat;
 use singbox::error::ConversionError;
 use singbox::protocol::Protocol;
-use std::path::PathBuf;
-use teloxide::{prelude::*, types::InputFile, types::Message, utils::command::BotCommands};
+use singbox::qr::QrCode;
+use singbox::xray::XrayConfig;
+use teloxide::{
+    prelude::*, types::ChatId, types::InputFile, types::Message, utils::command::BotCommands,
+};
 
 extern crate pretty_env_logger;
 
@@ -19,10 +26,16 @@ enum Command {
     Start,
     #[command(description = "Help")]
     Help,
-    #[command(description = "Process singbox URI - /singbox <version> <URI>")]
+    #[command(description = "Process singbox URI - /singbox <version> <URI> [platform]")]
     Singbox(String),
-    #[command(description = "Xray Comming Soon")]
-    Xray,
+    #[command(description = "Process xray URI - /xray <URI>")]
+    Xray(String),
+    #[command(description = "Process clash/mihomo URI - /clash <URI>")]
+    Clash(String),
+    #[command(description = "Export a WireGuard URI as a .conf file - /wireguard <URI>")]
+    Wireguard(String),
+    #[command(description = "Only the sing-box outbounds - /outbound <version> <URI> [<URI> ...]")]
+    Outbound(String),
 }
 
 #[tokio::main]
@@ -47,11 +60,9 @@ async fn main() {
         .await;
 }
 
-async fn schema(
-    bot: Bot,
-    msg: Message,
-    cmd: Command,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+type HandlerResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
+
+async fn schema(bot: Bot, msg: Message, cmd: Command) -> HandlerResult {
     match cmd {
         Command::Singbox(args) => {
             let parts: Vec<&str> = args.splitn(3, ' ').collect();
@@ -59,13 +70,31 @@ async fn schema(
             if parts.len() < 2 {
                 bot.send_message(
                     msg.chat.id,
-                    "Invalid format. Use /singbox <version> <URI>\nSupported versions: 1.11.0, 1.12.0",
+                    "Invalid format. Use /singbox <version> <URI> [platform]\nSupported versions: 1.11.0, 1.12.0",
                 )
                 .await?;
                 return Ok(());
             }
             let version = parts[0];
             let uri = parts[1];
+            let platform = match parts.get(2).map(|p| p.trim()).filter(|p| !p.is_empty()) {
+                Some(name) => match name.parse::<Platform>() {
+                    Ok(platform) => platform,
+                    Err(_) => {
+                        let names: Vec<&str> = PLATFORMS.iter().map(Platform::as_str).collect();
+                        bot.send_message(
+                            msg.chat.id,
+                            format!(
+                                "Unsupported platform. Currently supported: {}",
+                                names.join(", ")
+                            ),
+                        )
+                        .await?;
+                        return Ok(());
+                    }
+                },
+                None => Platform::default(),
+            };
 
             if !["1.11.0", "1.12.0"].contains(&version) {
                 bot.send_message(
@@ -76,28 +105,16 @@ async fn schema(
                 return Ok(());
             }
 
-            if uri.is_empty() {
-                bot.send_message(msg.chat.id, "URI cannot be empty").await?;
-                return Ok(());
-            }
-
-            if !utils::is_valid_uri(uri) {
-                bot.send_message(msg.chat.id, "❌ Invalid URI").await?;
-                return Ok(());
-            }
-
-            match process_uri(version, uri).await {
-                Ok(filename) => {
-                    let file = InputFile::file(PathBuf::from(&filename));
-                    bot.send_document(msg.chat.id, file).await?;
-                    utils::cleanup_file(&filename).await;
-                }
-                Err(e) => {
-                    bot.send_message(msg.chat.id, format!("❌ Error processing URI:: {}", e))
-                        .parse_mode(teloxide::types::ParseMode::MarkdownV2)
-                        .await?;
-                }
-            }
+            send_config(
+                &bot,
+                msg.chat.id,
+                &[uri],
+                "Invalid format. Use /singbox <version> <URI> [platform]",
+                timestamped("singbox_config", "json"),
+                true,
+                || process_uri(version, uri, platform),
+            )
+            .await?;
         }
 
         Command::Start => {
@@ -112,41 +129,196 @@ async fn schema(
                 .parse_mode(teloxide::types::ParseMode::MarkdownV2)
                 .await?;
         }
-        Command::Xray => {
-            let scapedtext =
-                utils::escape_markdown_v2("🚧 Xray support is coming soon. Stay tuned!");
-            bot.send_message(msg.chat.id, scapedtext)
-                .parse_mode(teloxide::types::ParseMode::MarkdownV2)
+        Command::Xray(uri) => {
+            let uri = uri.trim();
+            send_config(
+                &bot,
+                msg.chat.id,
+                &[uri],
+                "Invalid format. Use /xray <URI>",
+                timestamped("xray_config", "json"),
+                true,
+                || process_xray_uri(uri),
+            )
+            .await?;
+        }
+        Command::Clash(uri) => {
+            let uri = uri.trim();
+            send_config(
+                &bot,
+                msg.chat.id,
+                &[uri],
+                "Invalid format. Use /clash <URI>",
+                timestamped("clash_config", "yaml"),
+                true,
+                || process_clash_uri(uri),
+            )
+            .await?;
+        }
+        Command::Wireguard(uri) => {
+            let uri = uri.trim();
+            // The WireGuard apps name the tunnel after the file and reject
+            // names longer than 15 characters.
+            let file_name = format!("wg_{}.conf", Local::now().format("%m%d%H%M%S"));
+            send_config(
+                &bot,
+                msg.chat.id,
+                &[uri],
+                "Invalid format. Use /wireguard <URI>",
+                file_name,
+                false,
+                || process_wireguard_uri(uri),
+            )
+            .await?;
+        }
+        Command::Outbound(args) => {
+            let mut parts = args.split_whitespace();
+            let version = parts.next().unwrap_or_default();
+            let uris: Vec<&str> = parts.collect();
+
+            if !uris.is_empty() && !["1.11.0", "1.12.0"].contains(&version) {
+                bot.send_message(
+                    msg.chat.id,
+                    "Unsupported version. Currently supported: 1.11.0, 1.12.0",
+                )
                 .await?;
+                return Ok(());
+            }
+
+            send_config(
+                &bot,
+                msg.chat.id,
+                &uris,
+                "Invalid format. Use /outbound <version> <URI> [<URI> ...]\nSupported versions: 1.11.0, 1.12.0",
+                timestamped("singbox_outbound", "json"),
+                false,
+                || process_outbound_uris(version, &uris),
+            )
+            .await?;
         }
     }
     Ok(())
 }
 
-async fn process_uri(version: &str, uri: &str) -> Result<String, ConversionError> {
+// Shared by the conversion commands: checks the URIs, runs `process` and sends
+// its output back as `file_name`, followed by the share link as a QR code when
+// `qr` is set.
+async fn send_config(
+    bot: &Bot,
+    chat_id: ChatId,
+    uris: &[&str],
+    usage: &str,
+    file_name: String,
+    qr: bool,
+    process: impl FnOnce() -> Result<String, ConversionError>,
+) -> HandlerResult {
+    if uris.is_empty() || uris.iter().any(|uri| uri.is_empty()) {
+        bot.send_message(chat_id, usage).await?;
+        return Ok(());
+    }
+
+    if !uris.iter().all(|uri| utils::is_valid_uri(uri)) {
+        bot.send_message(chat_id, "❌ Invalid URI").await?;
+        return Ok(());
+    }
+
+    match process() {
+        Ok(content) => {
+            let file = InputFile::memory(content).file_name(file_name);
+            bot.send_document(chat_id, file).await?;
+            if qr {
+                for uri in uris {
+                    send_qr(bot, chat_id, uri).await?;
+                }
+            }
+        }
+        // Sent as plain text: the error quotes user input, which may contain
+        // MarkdownV2 syntax.
+        Err(e) => {
+            bot.send_message(chat_id, format!("❌ Error processing URI: {}", e))
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+fn timestamped(prefix: &str, extension: &str) -> String {
+    format!(
+        "{}_{}.{}",
+        prefix,
+        Local::now().format("%Y%m%d_%H%M%S"),
+        extension
+    )
+}
+
+// Sends the share link as a QR code next to the converted config. A link too
+// long for a QR code is not an error for the conversion itself.
+async fn send_qr(bot: &Bot, chat_id: ChatId, uri: &str) -> HandlerResult {
+    match QrCode::new(uri).and_then(|code| code.to_png(8)) {
+        Ok(png) => {
+            bot.send_photo(chat_id, InputFile::memory(png).file_name("qr.png"))
+                .await?;
+        }
+        Err(e) => log::warn!("Skipping QR code: {}", e),
+    }
+    Ok(())
+}
+
+fn process_uri(version: &str, uri: &str, platform: Platform) -> Result<String, ConversionError> {
     let protocol = Protocol::parse_uri(uri)?;
-    let mut config = match config::SingBoxConfig::new(version.to_string().clone()) {
-        Ok(config) => config,
-        Err(e) => return Err(ConversionError::Other(e.to_string())),
-    };
+    let mut config = config::SingBoxConfig::new(version.to_string())?.with_platform(platform);
     config.set_log_level("warn");
     config.add_dns_server();
     config.add_dns_rule();
 
     config.add_mixed_inbound();
     config.add_tun_inbound();
-    if let Err(e) = config.add_outbound(protocol) {
-        eprintln!("Failed to add outbound: {}", e);
-    }
+    config.add_outbound(protocol)?;
     config.set_route();
     config.add_default_experimental();
 
-    let timestamp = Local::now().format("%Y%m%d_%H%M%S");
-    let filename = format!("singbox_config_{}.json", timestamp);
+    config.to_string(OutputFormat::Pretty)
+}
 
-    if let Err(e) = config.save_to_file(&filename) {
-        eprintln!("Failed to save config to '{}': {}", filename, e);
-    }
+fn process_xray_uri(uri: &str) -> Result<String, ConversionError> {
+    let protocol = Protocol::parse_uri(uri)?;
+    let mut config = XrayConfig::new();
+    config.set_log_level("warning");
+    config.add_dns_server();
+    config.add_socks_inbound();
+    config.add_http_inbound();
+    config.add_outbound(protocol)?;
+    config.set_route();
+
+    config.to_json()
+}
+
+fn process_clash_uri(uri: &str) -> Result<String, ConversionError> {
+    let protocol = Protocol::parse_uri(uri)?;
+    let mut config = ClashConfig::new();
+    config.add_mixed_port();
+    config.set_log_level("warning");
+    config.add_dns_server();
+    config.add_proxy(protocol)?;
+    config.add_proxy_groups();
+    config.set_route();
+
+    config.to_yaml()
+}
+
+fn process_wireguard_uri(uri: &str) -> Result<String, ConversionError> {
+    Protocol::parse_uri(uri)?.to_wireguard_config()
+}
+
+// Each outbound is tagged with its node's remark, so the tag is chosen through
+// the `#fragment` of the URI.
+fn process_outbound_uris(version: &str, uris: &[&str]) -> Result<String, ConversionError> {
+    let protocols = uris
+        .iter()
+        .map(|uri| Protocol::parse_uri(uri))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut snippets = OutboundSnippets::new(version.to_string())?;
+    snippets.add_all(&protocols)?;
 
-    Ok(filename)
+    snippets.to_json()
 }
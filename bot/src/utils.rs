@@ -1,6 +1,3 @@
-use std::time::Duration;
-use tokio::{fs, time};
-
 pub fn welcome_message() -> String {
     r#"
 🎉 Welcome to the URI to JSON Bot!
@@ -11,14 +8,19 @@ I'm here to help you Convert URI into JSON configurations compatible with SingBo
 
 - /start — Show this welcome message
 - /help — Display all available commands
-- /singbox <version> <URI> — Process a URI for a specific version
+- /singbox <version> <URI> [platform] — Process a URI for a specific version and device
+- /xray <URI> — Generate an Xray client config
+- /clash <URI> — Generate a Clash Meta (mihomo) config
+- /wireguard <URI> — Export a WireGuard URI as a .conf file
+- /outbound <version> <URI> [<URI> ...] — Only the sing-box outbounds, as a JSON array tagged with each link's remark
+
+The /singbox, /xray and /clash replies also include a QR code of the link.
 🔍 Supported Versions:
 
 - 1.11.0
 - 1.12.0
 
-🚀 Coming Soon:
- - /xray command
+📱 Platforms: android (default), ios, windows, macos, linux, linux-router
 
 Beta Notice:
 "We're currently in beta! If you encounter any issues or have feedback, please let us know.
@@ -35,8 +37,13 @@ pub fn help_message() -> String {
     📋 Available commands:
             /start - Show this welcome message
             /help - Show available commands
-            /singbox <version> <URI> - Process URI for specific version
+            /singbox <version> <URI> [platform] - Process URI for specific version and device
+            /xray <URI> - Generate Xray client config
+            /clash <URI> - Generate Clash Meta (mihomo) config
+            /wireguard <URI> - Export WireGuard URI as a .conf file
+            /outbound <version> <URI> [<URI> ...] - Only the sing-box outbounds
     🔍 Supported versions: 1.11.0, 1.12.0
+    📱 Platforms: android (default), ios, windows, macos, linux, linux-router
     
     *Supported Protocols:*
     - `ss://` Shadowsocks
@@ -44,17 +51,15 @@ pub fn help_message() -> String {
     - `vless://` VLESS
     - `trojan://` Trojan
     - `wg://` WireGuard
+    - `hysteria2://` Hysteria2
+    - `tuic://` TUIC
+    - `ssr://` ShadowsocksR (Clash only)
     "#
     .trim()
     .replace("    ", "")
     .to_string()
 }
 
-pub async fn cleanup_file(filename: &str) {
-    time::sleep(Duration::from_secs(30)).await; // Keep file available for 30 seconds
-    let _ = fs::remove_file(filename).await;
-}
-
 pub fn escape_markdown_v2(text: &str) -> String {
     // Define the list of reserved MarkdownV2 characters.
     let reserved_chars: [char; 18] = [
@@ -80,4 +85,8 @@ pub fn is_valid_uri(uri: &str) -> bool {
         || uri.starts_with("wg://")
         || uri.starts_with("trojan://")
         || uri.starts_with("wireguard://")
+        || uri.starts_with("hysteria2://")
+        || uri.starts_with("hy2://")
+        || uri.starts_with("tuic://")
+        || uri.starts_with("ssr://")
 }
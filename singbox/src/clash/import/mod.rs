@@ -0,0 +1,510 @@
+use crate::error::ConversionError;
+use crate::fields::{non_empty, parse_int, parse_port, string};
+use crate::protocol::amnezia::AmneziaConfig;
+use crate::protocol::tls::{RealityConfig, TlsConfig, UTlsConfig};
+use crate::protocol::transport::TransportConfig;
+use crate::protocol::Protocol;
+use serde_json::Value;
+use std::collections::HashMap;
+
+// Reads a Clash / mihomo config or proxy-provider file and returns every
+// `proxies:` entry we can represent. Entries of unsupported types, or that
+// cannot be converted, are skipped with a warning so one bad node does not
+// lose the whole file.
+pub fn parse_config(content: &str) -> Result<Vec<Protocol>, ConversionError> {
+    let yaml: serde_yaml::Value =
+        serde_yaml::from_str(content).map_err(|e| ConversionError::ParseError(e.to_string()))?;
+    let config =
+        serde_json::to_value(yaml).map_err(|e| ConversionError::ParseError(e.to_string()))?;
+
+    let proxies = config["proxies"]
+        .as_array()
+        .ok_or(ConversionError::MissingField("proxies"))?;
+
+    let mut protocols = Vec::new();
+    for proxy in proxies {
+        match parse_proxy(proxy) {
+            Ok(protocol) => protocols.push(protocol),
+            Err(ConversionError::UnsupportedProtocol(proxy_type)) => {
+                log::warn!("Skipping unsupported Clash proxy type: {}", proxy_type);
+            }
+            Err(e) => {
+                log::warn!(
+                    "Skipping Clash proxy {}: {}",
+                    string(&proxy["name"]).unwrap_or_default(),
+                    e
+                );
+            }
+        }
+    }
+    Ok(protocols)
+}
+
+pub fn parse_proxy(proxy: &Value) -> Result<Protocol, ConversionError> {
+    let host = non_empty(&proxy["server"]).ok_or(ConversionError::MissingHost)?;
+    let port = parse_port(&proxy["port"])?;
+    let remark = non_empty(&proxy["name"]);
+
+    match proxy["type"].as_str().unwrap_or_default() {
+        "ss" => {
+            let (plugin, plugin_opts) = parse_plugin(proxy);
+            Ok(Protocol::Shadowsocks {
+                method: non_empty(&proxy["cipher"])
+                    .ok_or(ConversionError::MissingField("cipher"))?,
+                password: string(&proxy["password"]).ok_or(ConversionError::MissingPassword)?,
+                host,
+                port,
+                plugin,
+                plugin_opts,
+                remark,
+            })
+        }
+        "ssr" => Ok(Protocol::ShadowsocksR {
+            method: non_empty(&proxy["cipher"]).ok_or(ConversionError::MissingField("cipher"))?,
+            password: string(&proxy["password"]).ok_or(ConversionError::MissingPassword)?,
+            host,
+            port,
+            ssr_protocol: non_empty(&proxy["protocol"])
+                .ok_or(ConversionError::MissingField("protocol"))?,
+            protocol_param: non_empty(&proxy["protocol-param"]),
+            obfs: non_empty(&proxy["obfs"]).ok_or(ConversionError::MissingField("obfs"))?,
+            obfs_param: non_empty(&proxy["obfs-param"]),
+            remark,
+        }),
+        "vmess" => Ok(Protocol::Vmess {
+            uuid: non_empty(&proxy["uuid"]).ok_or(ConversionError::MissingUUID)?,
+            host,
+            port,
+            alter_id: string(&proxy["alterId"]).unwrap_or_else(|| "0".to_string()),
+            security: non_empty(&proxy["cipher"]).unwrap_or_else(|| "auto".to_string()),
+            packet_encoding: non_empty(&proxy["packet-encoding"])
+                .or_else(|| as_bool(&proxy["xudp"]).then(|| "xudp".to_string())),
+            transport: parse_transport(proxy)?,
+            tls: parse_tls(proxy, as_bool(&proxy["tls"]))?,
+            remark,
+        }),
+        "vless" => Ok(Protocol::Vless {
+            uuid: non_empty(&proxy["uuid"]).ok_or(ConversionError::MissingUUID)?,
+            host,
+            port,
+            flow: non_empty(&proxy["flow"]),
+            packet_encoding: non_empty(&proxy["packet-encoding"])
+                .or_else(|| as_bool(&proxy["xudp"]).then(|| "xudp".to_string())),
+            transport: parse_transport(proxy)?,
+            tls: parse_tls(proxy, as_bool(&proxy["tls"]))?,
+            remark,
+        }),
+        "trojan" => Ok(Protocol::Trojan {
+            password: string(&proxy["password"]).ok_or(ConversionError::MissingPassword)?,
+            host,
+            port,
+            transport: parse_transport(proxy)?,
+            tls: parse_tls(proxy, true)?,
+            remark,
+        }),
+        "hysteria2" => Ok(Protocol::Hysteria2 {
+            password: string(&proxy["password"]).ok_or(ConversionError::MissingPassword)?,
+            host,
+            port,
+            obfs: non_empty(&proxy["obfs"]),
+            obfs_password: non_empty(&proxy["obfs-password"]),
+            up_mbps: parse_mbps(&proxy["up"])?,
+            down_mbps: parse_mbps(&proxy["down"])?,
+            tls: parse_quic_tls(proxy),
+            remark,
+        }),
+        "tuic" => Ok(Protocol::Tuic {
+            uuid: non_empty(&proxy["uuid"]).ok_or(ConversionError::MissingUUID)?,
+            password: string(&proxy["password"]).ok_or(ConversionError::MissingPassword)?,
+            host,
+            port,
+            congestion_control: non_empty(&proxy["congestion-controller"]),
+            udp_relay_mode: non_empty(&proxy["udp-relay-mode"]),
+            tls: parse_quic_tls(proxy),
+            remark,
+        }),
+        "wireguard" => {
+            // mihomo accepts the peer inline or as the first entry of `peers`.
+            let peer = if proxy["peers"].is_array() {
+                &proxy["peers"][0]
+            } else {
+                proxy
+            };
+            let peer_host = non_empty(&peer["server"]).unwrap_or(host);
+            let peer_port = match &peer["port"] {
+                Value::Null => port,
+                peer_port => parse_port(peer_port)?,
+            };
+            let endpoint = if peer_host.contains(':') {
+                format!("[{}]:{}", peer_host, peer_port)
+            } else {
+                format!("{}:{}", peer_host, peer_port)
+            };
+            let with_prefix = |ip: String, bits: u8| {
+                if ip.contains('/') {
+                    ip
+                } else {
+                    format!("{}/{}", ip, bits)
+                }
+            };
+            let mut ip = with_prefix(
+                non_empty(&proxy["ip"]).ok_or(ConversionError::MissingIP)?,
+                32,
+            );
+            if let Some(ipv6) = non_empty(&proxy["ipv6"]) {
+                ip = format!("{},{}", ip, with_prefix(ipv6, 128));
+            }
+
+            Ok(Protocol::Wireguard {
+                private_key: non_empty(&proxy["private-key"])
+                    .ok_or(ConversionError::MissingField("private-key"))?,
+                public_key: non_empty(&peer["public-key"])
+                    .ok_or(ConversionError::MissingPublicKey)?,
+                endpoint,
+                dns: proxy["dns"]
+                    .as_array()
+                    .map(|dns| dns.iter().filter_map(string).collect::<Vec<_>>().join(",")),
+                mtu: parse_int(&proxy["mtu"], "mtu")?,
+                ip,
+                pre_shared_key: non_empty(&peer["pre-shared-key"]),
+                persistent_keepalive: parse_int(
+                    &proxy["persistent-keepalive"],
+                    "persistent-keepalive",
+                )?,
+                amnezia: AmneziaConfig::from_params(&string_map(&proxy["amnezia-wg-option"]))?,
+                remark,
+            })
+        }
+        proxy_type => Err(ConversionError::UnsupportedProtocol(proxy_type.to_string())),
+    }
+}
+
+fn parse_transport(proxy: &Value) -> Result<TransportConfig, ConversionError> {
+    let network = non_empty(&proxy["network"]).unwrap_or_else(|| "tcp".to_string());
+
+    match network.as_str() {
+        "tcp" => Ok(TransportConfig::Tcp),
+        "ws" => {
+            let opts = &proxy["ws-opts"];
+            let headers = string_map(&opts["headers"]);
+            let path = non_empty(&opts["path"])
+                .or_else(|| non_empty(&proxy["ws-path"]))
+                .unwrap_or_default();
+
+            if as_bool(&opts["v2ray-http-upgrade"]) {
+                let mut headers = headers;
+                return Ok(TransportConfig::Httpupgrade {
+                    host: headers.remove("Host").unwrap_or_default(),
+                    path,
+                    headers,
+                });
+            }
+
+            let max_early_data = parse_int(&opts["max-early-data"], "max-early-data")?.unwrap_or(0);
+            Ok(TransportConfig::Websocket {
+                path,
+                headers,
+                max_early_data,
+                early_data_header_name: non_empty(&opts["early-data-header-name"])
+                    .unwrap_or_default(),
+            })
+        }
+        "grpc" => Ok(TransportConfig::Grpc {
+            service_name: non_empty(&proxy["grpc-opts"]["grpc-service-name"]).unwrap_or_default(),
+            idle_timeout: Some("15s".to_string()),
+            ping_timeout: Some("15s".to_string()),
+            permit_without_stream: false,
+        }),
+        "h2" => {
+            let opts = &proxy["h2-opts"];
+            Ok(TransportConfig::Http {
+                host: string_list(&opts["host"]),
+                path: non_empty(&opts["path"]).unwrap_or_else(|| "/".to_string()),
+                method: "GET".to_string(),
+                headers: HashMap::new(),
+                idle_timeout: Some("15s".to_string()),
+                ping_timeout: Some("15s".to_string()),
+            })
+        }
+        "http" => {
+            let opts = &proxy["http-opts"];
+            let mut headers = string_map(&opts["headers"]);
+            let host = string_list(&opts["headers"]["Host"]);
+            headers.remove("Host");
+            Ok(TransportConfig::Http {
+                host,
+                path: string_list(&opts["path"])
+                    .into_iter()
+                    .next()
+                    .unwrap_or_else(|| "/".to_string()),
+                method: non_empty(&opts["method"]).unwrap_or_else(|| "GET".to_string()),
+                headers,
+                idle_timeout: Some("15s".to_string()),
+                ping_timeout: Some("15s".to_string()),
+            })
+        }
+        _ => Err(ConversionError::InvalidTransportType(network)),
+    }
+}
+
+fn parse_tls(proxy: &Value, enabled: bool) -> Result<TlsConfig, ConversionError> {
+    let reality_opts = &proxy["reality-opts"];
+    if !enabled && !reality_opts.is_object() {
+        return Ok(TlsConfig::default());
+    }
+
+    let reality = if reality_opts.is_object() {
+        Some(RealityConfig {
+            public_key: non_empty(&reality_opts["public-key"]).ok_or(
+                ConversionError::MissingRealityParam("public-key".to_string()),
+            )?,
+            short_id: non_empty(&reality_opts["short-id"]).unwrap_or_default(),
+        })
+    } else {
+        None
+    };
+
+    Ok(TlsConfig {
+        utls: Some(UTlsConfig {
+            enabled: true,
+            fingerprint: non_empty(&proxy["client-fingerprint"])
+                .unwrap_or_else(|| "chrome".to_string()),
+        }),
+        reality,
+        ..parse_quic_tls(proxy)
+    })
+}
+
+// Hysteria2 and TUIC always use TLS and have no uTLS fingerprint.
+fn parse_quic_tls(proxy: &Value) -> TlsConfig {
+    TlsConfig {
+        enabled: true,
+        insecure: as_bool(&proxy["skip-cert-verify"]),
+        sni: non_empty(&proxy["servername"]).or_else(|| non_empty(&proxy["sni"])),
+        alpn: string_list(&proxy["alpn"])
+            .into_iter()
+            .map(|a| a.to_lowercase())
+            .collect(),
+        utls: None,
+        reality: None,
+    }
+}
+
+// Clash keeps SIP003 plugin options as a map; sing-box and share links want the
+// `key=value;flag` string form.
+fn parse_plugin(proxy: &Value) -> (Option<String>, Option<String>) {
+    let Some(plugin) = non_empty(&proxy["plugin"]) else {
+        return (None, None);
+    };
+    let opts = &proxy["plugin-opts"];
+
+    let (plugin, pairs) = match plugin.as_str() {
+        "obfs" => (
+            "obfs-local".to_string(),
+            vec![
+                non_empty(&opts["mode"]).map(|mode| format!("obfs={}", mode)),
+                non_empty(&opts["host"]).map(|host| format!("obfs-host={}", host)),
+            ],
+        ),
+        "v2ray-plugin" => (
+            plugin,
+            vec![
+                non_empty(&opts["mode"]).map(|mode| format!("mode={}", mode)),
+                as_bool(&opts["tls"]).then(|| "tls".to_string()),
+                non_empty(&opts["host"]).map(|host| format!("host={}", host)),
+                non_empty(&opts["path"]).map(|path| format!("path={}", path)),
+            ],
+        ),
+        _ => (
+            plugin,
+            opts.as_object()
+                .map(|opts| {
+                    opts.iter()
+                        .filter_map(|(k, v)| string(v).map(|v| Some(format!("{}={}", k, v))))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        ),
+    };
+
+    let opts = pairs.into_iter().flatten().collect::<Vec<_>>().join(";");
+    (Some(plugin), Some(opts).filter(|o| !o.is_empty()))
+}
+
+// Bandwidth is written either as a number of Mbps or as "100 Mbps".
+fn parse_mbps(value: &Value) -> Result<Option<u32>, ConversionError> {
+    let Some(bandwidth) = value.as_str() else {
+        return parse_int(value, "bandwidth");
+    };
+    let invalid = || ConversionError::ParseError(format!("invalid bandwidth: {}", bandwidth));
+
+    let bandwidth = bandwidth.trim();
+    let split = bandwidth
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(bandwidth.len());
+    let (number, unit) = bandwidth.split_at(split);
+    let number: u32 = number.parse().map_err(|_| invalid())?;
+    let scale = match unit.trim().to_lowercase().as_str() {
+        "" | "mbps" => 1,
+        "gbps" => 1000,
+        _ => return Err(invalid()),
+    };
+    number.checked_mul(scale).map(Some).ok_or_else(invalid)
+}
+
+fn string_map(value: &Value) -> HashMap<String, String> {
+    value
+        .as_object()
+        .map(|map| {
+            map.iter()
+                .filter_map(|(k, v)| {
+                    string(v)
+                        .or_else(|| string_list(v).into_iter().next())
+                        .map(|v| (k.clone(), v))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn string_list(value: &Value) -> Vec<String> {
+    match value {
+        Value::Array(values) => values.iter().filter_map(non_empty).collect(),
+        value => non_empty(value).into_iter().collect(),
+    }
+}
+
+fn as_bool(value: &Value) -> bool {
+    value.as_bool().unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn bad_proxies_are_skipped() {
+        let config = r#"
+proxies:
+  - { name: ok, type: trojan, server: example.com, port: 443, password: secret }
+  - { name: no-password, type: trojan, server: example.com, port: 443 }
+  - { name: socks, type: socks5, server: example.com, port: 1080 }
+"#;
+        let protocols = parse_config(config).unwrap();
+        assert_eq!(protocols.len(), 1);
+        assert_eq!(protocols[0].remark(), Some("ok"));
+    }
+
+    #[test]
+    fn out_of_range_numbers_are_rejected() {
+        assert_eq!(parse_port(&json!(443)).unwrap(), 443);
+        assert_eq!(parse_port(&json!("8443")).unwrap(), 8443);
+        assert!(matches!(
+            parse_port(&Value::Null),
+            Err(ConversionError::MissingPort)
+        ));
+        assert!(parse_port(&json!(65536)).is_err());
+        assert!(parse_port(&json!("70000")).is_err());
+
+        let wireguard = json!({
+            "name": "wg",
+            "type": "wireguard",
+            "server": "example.com",
+            "port": 51820,
+            "ip": "10.0.0.2",
+            "private-key": "private",
+            "public-key": "public",
+            "mtu": 70000
+        });
+        assert!(parse_proxy(&wireguard).is_err());
+        assert!(parse_mbps(&json!(u64::MAX)).is_err());
+    }
+
+    #[test]
+    fn bandwidth_units_are_read() {
+        assert_eq!(parse_mbps(&json!(50)).unwrap(), Some(50));
+        assert_eq!(parse_mbps(&json!("100")).unwrap(), Some(100));
+        assert_eq!(parse_mbps(&json!("100 Mbps")).unwrap(), Some(100));
+        assert_eq!(parse_mbps(&json!("1 Gbps")).unwrap(), Some(1000));
+        assert_eq!(parse_mbps(&json!("2gbps")).unwrap(), Some(2000));
+        assert_eq!(parse_mbps(&Value::Null).unwrap(), None);
+
+        for bad in ["fast", "100 Kbps", "Mbps", "1.5 Gbps", "5000000 Gbps"] {
+            assert!(parse_mbps(&json!(bad)).is_err(), "{} was accepted", bad);
+        }
+    }
+
+    #[test]
+    fn transport_and_tls_options_are_mapped() {
+        let ws = json!({
+            "name": "ws",
+            "type": "vmess",
+            "server": "example.com",
+            "port": 443,
+            "uuid": "uuid",
+            "tls": true,
+            "servername": "sni.example.com",
+            "skip-cert-verify": true,
+            "alpn": ["h2"],
+            "client-fingerprint": "safari",
+            "network": "ws",
+            "ws-opts": {
+                "path": "/ws",
+                "headers": { "Host": "cdn.example.com" },
+                "max-early-data": 2048,
+                "early-data-header-name": "Sec-WebSocket-Protocol"
+            }
+        });
+        let Protocol::Vmess { transport, tls, .. } = parse_proxy(&ws).unwrap() else {
+            panic!("expected VMess");
+        };
+        assert_eq!(
+            transport,
+            TransportConfig::Websocket {
+                path: "/ws".to_string(),
+                headers: HashMap::from([("Host".to_string(), "cdn.example.com".to_string())]),
+                max_early_data: 2048,
+                early_data_header_name: "Sec-WebSocket-Protocol".to_string(),
+            }
+        );
+        assert!(tls.enabled && tls.insecure);
+        assert_eq!(tls.sni.as_deref(), Some("sni.example.com"));
+        assert_eq!(tls.alpn, vec!["h2"]);
+        assert_eq!(tls.fingerprint(), Some("safari"));
+
+        let reality = json!({
+            "name": "reality",
+            "type": "vless",
+            "server": "example.com",
+            "port": 443,
+            "uuid": "uuid",
+            "tls": true,
+            "network": "grpc",
+            "grpc-opts": { "grpc-service-name": "tunnel" },
+            "reality-opts": { "public-key": "pbk", "short-id": "ab12" }
+        });
+        let Protocol::Vless { transport, tls, .. } = parse_proxy(&reality).unwrap() else {
+            panic!("expected VLESS");
+        };
+        let TransportConfig::Grpc { service_name, .. } = transport else {
+            panic!("expected gRPC, got {:?}", transport);
+        };
+        assert_eq!(service_name, "tunnel");
+        assert_eq!(
+            tls.reality,
+            Some(RealityConfig {
+                public_key: "pbk".to_string(),
+                short_id: "ab12".to_string(),
+            })
+        );
+        assert_eq!(tls.fingerprint(), Some("chrome"));
+
+        let mut missing_key = reality;
+        missing_key["reality-opts"] = json!({ "short-id": "ab12" });
+        assert!(matches!(
+            parse_proxy(&missing_key),
+            Err(ConversionError::MissingRealityParam(_))
+        ));
+    }
+}
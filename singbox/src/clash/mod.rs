@@ -0,0 +1,354 @@
+pub mod import;
+pub mod proxy;
+use crate::error::ConversionError;
+use crate::protocol::Protocol;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::fs;
+use std::path::Path;
+
+// Proxy group every rule routes through; it selects between the nodes and the
+// automatic groups built by `add_proxy_groups`.
+const PROXY_GROUP: &str = "PROXY";
+
+const RULE_SET_BASE_URL: &str = "https://github.com/MetaCubeX/meta-rules-dat/raw/meta/geo";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ClashConfig {
+    #[serde(flatten)]
+    general: Map<String, Value>,
+    #[serde(skip_serializing_if = "Value::is_null")]
+    dns: Value,
+    proxies: Vec<Value>,
+    #[serde(rename = "proxy-groups")]
+    proxy_groups: Vec<Value>,
+    #[serde(rename = "rule-providers", skip_serializing_if = "Map::is_empty")]
+    rule_providers: Map<String, Value>,
+    rules: Vec<String>,
+}
+
+impl ClashConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn general(&self) -> &Map<String, Value> {
+        &self.general
+    }
+
+    pub fn dns(&self) -> &Value {
+        &self.dns
+    }
+
+    pub fn proxies(&self) -> &[Value] {
+        &self.proxies
+    }
+
+    pub fn proxy_groups(&self) -> &[Value] {
+        &self.proxy_groups
+    }
+
+    pub fn rule_providers(&self) -> &Map<String, Value> {
+        &self.rule_providers
+    }
+
+    pub fn rules(&self) -> &[String] {
+        &self.rules
+    }
+
+    pub fn with_general(mut self, key: &str, value: Value) -> Self {
+        self.general.insert(key.to_string(), value);
+        self
+    }
+
+    pub fn with_dns(mut self, dns: Value) -> Self {
+        self.dns = dns;
+        self
+    }
+
+    pub fn with_proxy_group(mut self, group: Value) -> Self {
+        self.proxy_groups.push(group);
+        self
+    }
+
+    pub fn with_rule(mut self, rule: &str) -> Self {
+        self.rules.push(rule.to_string());
+        self
+    }
+
+    // mihomo log levels: silent, error, warning, info, debug.
+    pub fn set_log_level(&mut self, level: &str) -> &mut Self {
+        self.general.insert("log-level".to_string(), json!(level));
+        self
+    }
+
+    pub fn add_mixed_port(&mut self) -> &mut Self {
+        self.general.insert("mixed-port".to_string(), json!(7890));
+        self.general.insert("allow-lan".to_string(), json!(false));
+        self.general.insert("mode".to_string(), json!("rule"));
+        self
+    }
+
+    // Same resolvers as the sing-box preset: AdGuard for everything, Cloudflare
+    // for Iranian domains and the hosts needed before the proxy is up.
+    pub fn add_dns_server(&mut self) -> &mut Self {
+        self.dns = json!({
+            "enable": true,
+            "ipv6": true,
+            "enhanced-mode": "fake-ip",
+            "fake-ip-range": "198.18.0.1/16",
+            "default-nameserver": ["1.1.1.1"],
+            "nameserver": ["tls://dns.adguard-dns.com"],
+            "proxy-server-nameserver": ["tls://1.1.1.1"],
+            "nameserver-policy": {
+                "rule-set:geosite-category-ir": ["tls://1.1.1.1"],
+                "raw.githubusercontent.com": ["tls://1.1.1.1"],
+                "time.apple.com": ["tls://1.1.1.1"]
+            }
+        });
+        self
+    }
+
+    pub fn add_proxy(&mut self, protocol: Protocol) -> Result<(), ConversionError> {
+        protocol.validate()?;
+        let mut proxy = protocol.to_clash_proxy()?;
+
+        // Clash refuses configs with duplicate proxy names.
+        let name = proxy["name"].as_str().unwrap_or_default().to_string();
+        let mut unique = name.clone();
+        let mut suffix = 2;
+        while self.proxy_names().contains(&unique) {
+            unique = format!("{} {}", name, suffix);
+            suffix += 1;
+        }
+        proxy["name"] = json!(unique);
+
+        self.proxies.push(proxy);
+        Ok(())
+    }
+
+    // A selector plus url-test, fallback and load-balance groups over every
+    // proxy added so far. Clash rejects empty groups, so with no proxies the
+    // selector only offers DIRECT.
+    pub fn add_proxy_groups(&mut self) -> &mut Self {
+        let names = self.proxy_names();
+        if names.is_empty() {
+            self.proxy_groups = vec![json!({
+                "name": PROXY_GROUP,
+                "type": "select",
+                "proxies": ["DIRECT"],
+            })];
+            return self;
+        }
+
+        let health_check = |group_type: &str, name: &str| {
+            json!({
+                "name": name,
+                "type": group_type,
+                "proxies": names,
+                "url": "https://www.gstatic.com/generate_204",
+                "interval": 300,
+            })
+        };
+
+        let mut selector = vec!["auto", "fallback", "load-balance"]
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        selector.extend(names.iter().cloned());
+
+        let mut load_balance = health_check("load-balance", "load-balance");
+        load_balance["strategy"] = json!("consistent-hashing");
+
+        self.proxy_groups = vec![
+            json!({
+                "name": PROXY_GROUP,
+                "type": "select",
+                "proxies": selector,
+            }),
+            health_check("url-test", "auto"),
+            health_check("fallback", "fallback"),
+            load_balance,
+        ];
+        self
+    }
+
+    // Mirrors `SingBoxConfig::set_route`: private and Iranian destinations
+    // direct, ads and trackers rejected, QUIC blocked, everything else proxied.
+    pub fn set_route(&mut self) -> &mut Self {
+        self.rule_providers = Map::new();
+        for (tag, behavior) in [
+            ("geoip-private", "ipcidr"),
+            ("geosite-category-public-tracker", "domain"),
+            ("geosite-category-ads", "domain"),
+            ("geosite-category-ads-all", "domain"),
+            ("geosite-google-ads", "domain"),
+            ("geosite-category-ir", "domain"),
+            ("geoip-ir", "ipcidr"),
+        ] {
+            let (kind, name) = tag.split_once('-').unwrap_or_default();
+            self.rule_providers.insert(
+                tag.to_string(),
+                json!({
+                    "type": "http",
+                    "behavior": behavior,
+                    "format": "mrs",
+                    "url": format!("{}/{}/{}.mrs", RULE_SET_BASE_URL, kind, name),
+                    "path": format!("./ruleset/{}.mrs", tag),
+                    "interval": 86400,
+                }),
+            );
+        }
+
+        self.rules = vec![
+            "RULE-SET,geoip-private,DIRECT,no-resolve".to_string(),
+            "RULE-SET,geosite-category-public-tracker,REJECT".to_string(),
+            "RULE-SET,geosite-category-ads,REJECT".to_string(),
+            "RULE-SET,geosite-category-ads-all,REJECT".to_string(),
+            "RULE-SET,geosite-google-ads,REJECT".to_string(),
+            "RULE-SET,geosite-category-ir,DIRECT".to_string(),
+            "RULE-SET,geoip-ir,DIRECT".to_string(),
+            "AND,((NETWORK,UDP),(DST-PORT,443)),REJECT".to_string(),
+            format!("MATCH,{}", PROXY_GROUP),
+        ];
+        self
+    }
+
+    pub fn to_yaml(&self) -> Result<String, ConversionError> {
+        serde_yaml::to_string(self).map_err(|e| ConversionError::SerializationError(e.to_string()))
+    }
+
+    pub fn save_to_file(&self, filename: &str) -> Result<(), ConversionError> {
+        fs::write(Path::new(filename), self.to_yaml()?)
+            .map_err(|e| ConversionError::IoError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    fn proxy_names(&self) -> Vec<String> {
+        self.proxies
+            .iter()
+            .filter_map(|proxy| proxy["name"].as_str().map(String::from))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::tls::TlsConfig;
+    use crate::protocol::transport::TransportConfig;
+
+    fn trojan(name: &str) -> Protocol {
+        Protocol::Trojan {
+            password: "secret".to_string(),
+            host: "example.com".to_string(),
+            port: 443,
+            transport: TransportConfig::Tcp,
+            tls: TlsConfig {
+                enabled: true,
+                ..Default::default()
+            },
+            remark: Some(name.to_string()),
+        }
+    }
+
+    fn group<'a>(config: &'a ClashConfig, name: &str) -> &'a Value {
+        config
+            .proxy_groups()
+            .iter()
+            .find(|group| group["name"] == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn proxy_groups_cover_every_proxy() {
+        let mut config = ClashConfig::new();
+        config.add_proxy(trojan("a")).unwrap();
+        config.add_proxy(trojan("a")).unwrap();
+        config.add_proxy_groups();
+
+        assert_eq!(
+            group(&config, PROXY_GROUP)["proxies"],
+            json!(["auto", "fallback", "load-balance", "a", "a 2"])
+        );
+        for (name, kind) in [
+            ("auto", "url-test"),
+            ("fallback", "fallback"),
+            ("load-balance", "load-balance"),
+        ] {
+            assert_eq!(group(&config, name)["type"], kind);
+            assert_eq!(group(&config, name)["proxies"], json!(["a", "a 2"]));
+        }
+        assert_eq!(
+            group(&config, "load-balance")["strategy"],
+            "consistent-hashing"
+        );
+    }
+
+    #[test]
+    fn no_proxies_fall_back_to_direct() {
+        let mut config = ClashConfig::new();
+        config.add_proxy_groups();
+        assert_eq!(
+            config.proxy_groups(),
+            [json!({ "name": PROXY_GROUP, "type": "select", "proxies": ["DIRECT"] })]
+        );
+    }
+
+    #[test]
+    fn every_rule_set_has_a_provider() {
+        let mut config = ClashConfig::new();
+        config.set_route();
+        for rule in config.rules() {
+            if let Some(rule) = rule.strip_prefix("RULE-SET,") {
+                let tag = rule.split(',').next().unwrap();
+                let provider = &config.rule_providers()[tag];
+                assert_eq!(provider["format"], "mrs");
+                assert_eq!(provider["path"], format!("./ruleset/{}.mrs", tag));
+                let expected = if tag.starts_with("geoip-") {
+                    "ipcidr"
+                } else {
+                    "domain"
+                };
+                assert_eq!(provider["behavior"], expected);
+                assert!(provider["url"].as_str().unwrap().ends_with(".mrs"));
+            }
+        }
+        assert_eq!(config.rule_providers().len(), 7);
+        assert_eq!(
+            config.rules()[0],
+            "RULE-SET,geoip-private,DIRECT,no-resolve"
+        );
+    }
+
+    #[test]
+    fn yaml_uses_clash_section_names() {
+        let mut config = ClashConfig::new();
+        config.add_mixed_port();
+        config.add_proxy(trojan("a")).unwrap();
+        config.add_proxy_groups().set_route();
+        let yaml: Value = serde_yaml::from_str(&config.to_yaml().unwrap()).unwrap();
+        assert_eq!(yaml["mixed-port"], 7890);
+        assert_eq!(yaml["proxies"][0]["name"], "a");
+        assert!(yaml["proxy-groups"].is_array());
+        assert!(yaml["rule-providers"].is_object());
+        assert_eq!(
+            yaml["rules"].as_array().unwrap().last().unwrap(),
+            "MATCH,PROXY"
+        );
+    }
+
+    #[test]
+    fn quic_is_rejected_before_the_final_match() {
+        let mut config = ClashConfig::new();
+        config.set_route();
+        let rules = config.rules();
+        let quic = rules
+            .iter()
+            .position(|rule| rule == "AND,((NETWORK,UDP),(DST-PORT,443)),REJECT")
+            .unwrap();
+        assert_eq!(quic, rules.len() - 2);
+        assert!(rules.last().unwrap().starts_with("MATCH,"));
+    }
+}
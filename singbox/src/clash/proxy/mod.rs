@@ -0,0 +1,442 @@
+use crate::error::ConversionError;
+use crate::protocol::tls::TlsConfig;
+use crate::protocol::transport::TransportConfig;
+use crate::protocol::{parse_alter_id, plugin_options, split_addresses, Protocol, SIMPLE_OBFS};
+use serde_json::{json, Map, Value};
+
+impl Protocol {
+    // A mihomo `proxies:` entry. The name is the remark, or the server address
+    // when the node has none; `ClashConfig` makes names unique.
+    pub fn to_clash_proxy(&self) -> Result<Value, ConversionError> {
+        let mut proxy = match self {
+            Self::Shadowsocks {
+                method,
+                password,
+                host,
+                port,
+                plugin,
+                plugin_opts,
+                ..
+            } => {
+                let mut proxy = json!({
+                    "type": "ss",
+                    "server": host,
+                    "port": port,
+                    "cipher": method,
+                    "password": password,
+                    "udp": true,
+                });
+                if let Some(plugin) = plugin {
+                    let (plugin, opts) = clash_plugin(plugin, plugin_opts.as_deref())?;
+                    proxy["plugin"] = json!(plugin);
+                    proxy["plugin-opts"] = opts;
+                }
+                proxy
+            }
+            Self::Vmess {
+                uuid,
+                host,
+                port,
+                alter_id,
+                security,
+                transport,
+                tls,
+                ..
+            } => {
+                let mut proxy = json!({
+                    "type": "vmess",
+                    "server": host,
+                    "port": port,
+                    "uuid": uuid,
+                    "alterId": parse_alter_id(alter_id)?,
+                    "cipher": security,
+                    "udp": true,
+                });
+                add_transport(&mut proxy, transport, tls)?;
+                proxy["tls"] = json!(tls.enabled);
+                add_tls(&mut proxy, tls, "servername");
+                proxy
+            }
+            Self::Vless {
+                uuid,
+                host,
+                port,
+                flow,
+                packet_encoding,
+                transport,
+                tls,
+                ..
+            } => {
+                let mut proxy = json!({
+                    "type": "vless",
+                    "server": host,
+                    "port": port,
+                    "uuid": uuid,
+                    "udp": true,
+                });
+                if let Some(flow) = flow {
+                    proxy["flow"] = json!(flow);
+                }
+                if let Some(packet_encoding) = packet_encoding {
+                    proxy["packet-encoding"] = json!(packet_encoding);
+                }
+                add_transport(&mut proxy, transport, tls)?;
+                proxy["tls"] = json!(tls.enabled);
+                add_tls(&mut proxy, tls, "servername");
+                proxy
+            }
+            Self::Trojan {
+                password,
+                host,
+                port,
+                transport,
+                tls,
+                ..
+            } => {
+                let mut proxy = json!({
+                    "type": "trojan",
+                    "server": host,
+                    "port": port,
+                    "password": password,
+                    "udp": true,
+                });
+                add_transport(&mut proxy, transport, tls)?;
+                add_tls(&mut proxy, tls, "sni");
+                proxy
+            }
+            Self::Wireguard {
+                private_key,
+                public_key,
+                endpoint,
+                dns,
+                mtu,
+                ip,
+                pre_shared_key,
+                persistent_keepalive,
+                amnezia,
+                ..
+            } => {
+                let (server, port) = endpoint
+                    .rsplit_once(':')
+                    .and_then(|(host, port)| Some((host, port.parse::<u16>().ok()?)))
+                    .ok_or(ConversionError::MissingPort)?;
+                let mut proxy = json!({
+                    "type": "wireguard",
+                    "server": server.trim_start_matches('[').trim_end_matches(']'),
+                    "port": port,
+                    "private-key": private_key,
+                    "public-key": public_key,
+                    "udp": true,
+                });
+                // Clash takes a single address per family; any further ones
+                // are dropped rather than overwriting the first.
+                for address in split_addresses(ip) {
+                    let key = if address.contains(':') { "ipv6" } else { "ip" };
+                    if proxy.get(key).is_some() {
+                        log::warn!("Clash keeps one {} address, dropping {}", key, address);
+                    } else {
+                        proxy[key] = json!(address);
+                    }
+                }
+                if let Some(mtu) = mtu {
+                    proxy["mtu"] = json!(mtu);
+                }
+                if let Some(dns) = dns {
+                    proxy["dns"] = json!(dns.split(',').map(str::trim).collect::<Vec<_>>());
+                }
+                if let Some(pre_shared_key) = pre_shared_key {
+                    proxy["pre-shared-key"] = json!(pre_shared_key);
+                }
+                if let Some(keepalive) = persistent_keepalive {
+                    proxy["persistent-keepalive"] = json!(keepalive);
+                }
+                if let Some(amnezia) = amnezia {
+                    proxy["amnezia-wg-option"] = amnezia.to_clash_option();
+                }
+                proxy
+            }
+            Self::Hysteria2 {
+                password,
+                host,
+                port,
+                obfs,
+                obfs_password,
+                up_mbps,
+                down_mbps,
+                tls,
+                ..
+            } => {
+                let mut proxy = json!({
+                    "type": "hysteria2",
+                    "server": host,
+                    "port": port,
+                    "password": password,
+                });
+                if let Some(obfs) = obfs {
+                    proxy["obfs"] = json!(obfs);
+                }
+                if let Some(obfs_password) = obfs_password {
+                    proxy["obfs-password"] = json!(obfs_password);
+                }
+                if let Some(up) = up_mbps {
+                    proxy["up"] = json!(format!("{} Mbps", up));
+                }
+                if let Some(down) = down_mbps {
+                    proxy["down"] = json!(format!("{} Mbps", down));
+                }
+                add_tls(&mut proxy, tls, "sni");
+                proxy
+            }
+            Self::Tuic {
+                uuid,
+                password,
+                host,
+                port,
+                congestion_control,
+                udp_relay_mode,
+                tls,
+                ..
+            } => {
+                let mut proxy = json!({
+                    "type": "tuic",
+                    "server": host,
+                    "port": port,
+                    "uuid": uuid,
+                    "password": password,
+                });
+                if let Some(congestion_control) = congestion_control {
+                    proxy["congestion-controller"] = json!(congestion_control);
+                }
+                if let Some(udp_relay_mode) = udp_relay_mode {
+                    proxy["udp-relay-mode"] = json!(udp_relay_mode);
+                }
+                add_tls(&mut proxy, tls, "sni");
+                proxy
+            }
+            Self::ShadowsocksR {
+                method,
+                password,
+                host,
+                port,
+                ssr_protocol,
+                protocol_param,
+                obfs,
+                obfs_param,
+                ..
+            } => {
+                let mut proxy = json!({
+                    "type": "ssr",
+                    "server": host,
+                    "port": port,
+                    "cipher": method,
+                    "password": password,
+                    "obfs": obfs,
+                    "protocol": ssr_protocol,
+                    "udp": true,
+                });
+                if let Some(obfs_param) = obfs_param {
+                    proxy["obfs-param"] = json!(obfs_param);
+                }
+                if let Some(protocol_param) = protocol_param {
+                    proxy["protocol-param"] = json!(protocol_param);
+                }
+                proxy
+            }
+        };
+
+        if let Some(map) = proxy.as_object_mut() {
+            let name = self.display_name();
+            // Keep `name` first, as every Clash config in the wild does.
+            let mut named = Map::new();
+            named.insert("name".to_string(), json!(name));
+            named.append(map);
+            *map = named;
+        }
+        Ok(proxy)
+    }
+}
+
+fn add_transport(
+    proxy: &mut Value,
+    transport: &TransportConfig,
+    tls: &TlsConfig,
+) -> Result<(), ConversionError> {
+    match transport {
+        TransportConfig::Tcp => {}
+        TransportConfig::Websocket {
+            path,
+            headers,
+            max_early_data,
+            early_data_header_name,
+        } => {
+            let mut opts = json!({
+                "path": path,
+                "headers": headers,
+            });
+            if *max_early_data > 0 {
+                opts["max-early-data"] = json!(max_early_data);
+                opts["early-data-header-name"] = json!(early_data_header_name);
+            }
+            proxy["network"] = json!("ws");
+            proxy["ws-opts"] = opts;
+        }
+        TransportConfig::Httpupgrade {
+            host,
+            path,
+            headers,
+        } => {
+            let mut headers = headers.clone();
+            if !host.is_empty() {
+                headers.insert("Host".to_string(), host.clone());
+            }
+            proxy["network"] = json!("ws");
+            proxy["ws-opts"] = json!({
+                "path": path,
+                "headers": headers,
+                "v2ray-http-upgrade": true,
+            });
+        }
+        TransportConfig::Grpc { service_name, .. } => {
+            proxy["network"] = json!("grpc");
+            proxy["grpc-opts"] = json!({ "grpc-service-name": service_name });
+        }
+        // Over TLS the http transport is HTTP/2; in the clear it is the
+        // HTTP/1.1 header obfuscation mihomo calls `http`.
+        TransportConfig::Http {
+            host,
+            path,
+            method,
+            headers,
+            ..
+        } => {
+            if tls.enabled {
+                proxy["network"] = json!("h2");
+                proxy["h2-opts"] = json!({
+                    "host": host,
+                    "path": path,
+                });
+            } else {
+                let mut headers = headers
+                    .iter()
+                    .map(|(k, v)| (k.clone(), json!([v])))
+                    .collect::<Map<String, Value>>();
+                if !host.is_empty() {
+                    headers.insert("Host".to_string(), json!(host));
+                }
+                proxy["network"] = json!("http");
+                proxy["http-opts"] = json!({
+                    "method": method,
+                    "path": [path],
+                    "headers": headers,
+                });
+            }
+        }
+        TransportConfig::Quic { .. }
+        | TransportConfig::Xhttp { .. }
+        | TransportConfig::Kcp { .. } => {
+            return Err(ConversionError::UnsupportedFeature(
+                "mihomo does not support the quic, xhttp or kcp transports".to_string(),
+            ))
+        }
+    }
+    Ok(())
+}
+
+// Only VMess and VLESS have a `tls` switch; the other protocols are always TLS.
+fn add_tls(proxy: &mut Value, tls: &TlsConfig, sni_key: &str) {
+    if !tls.enabled {
+        return;
+    }
+    if let Some(sni) = &tls.sni {
+        proxy[sni_key] = json!(sni);
+    }
+    if tls.insecure {
+        proxy["skip-cert-verify"] = json!(true);
+    }
+    if !tls.alpn.is_empty() {
+        proxy["alpn"] = json!(tls.alpn);
+    }
+    if let Some(fingerprint) = tls.fingerprint() {
+        proxy["client-fingerprint"] = json!(fingerprint);
+    }
+    if let Some(reality) = &tls.reality {
+        proxy["reality-opts"] = json!({
+            "public-key": reality.public_key,
+            "short-id": reality.short_id,
+        });
+    }
+}
+
+// SIP003 plugin strings (`obfs=http;obfs-host=x`) become mihomo's option maps.
+fn clash_plugin(plugin: &str, opts: Option<&str>) -> Result<(String, Value), ConversionError> {
+    let options = plugin_options(opts);
+    let option = |key: &str, default: &str| json!(options.get(key).map_or(default, String::as_str));
+
+    if SIMPLE_OBFS.contains(&plugin) {
+        return Ok((
+            "obfs".to_string(),
+            json!({
+                "mode": option("obfs", "http"),
+                "host": option("obfs-host", ""),
+            }),
+        ));
+    }
+    match plugin {
+        "v2ray-plugin" => {
+            let mut opts = json!({
+                "mode": option("mode", "websocket"),
+                "tls": options.contains_key("tls"),
+            });
+            for key in ["host", "path"] {
+                if let Some(value) = options.get(key) {
+                    opts[key] = json!(value);
+                }
+            }
+            Ok((plugin.to_string(), opts))
+        }
+        _ => Err(ConversionError::UnsupportedFeature(format!(
+            "mihomo does not support the {} plugin",
+            plugin
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wireguard_keeps_the_first_address_of_each_family() {
+        let protocol = Protocol::parse_uri(
+            "wireguard://priv@198.51.100.1:51820?publickey=pub&ip=10.0.0.2/32,fd00::2/128,10.0.0.3/32,fd00::3/128",
+        )
+        .unwrap();
+        let proxy = protocol.to_clash_proxy().unwrap();
+        assert_eq!(proxy["ip"], "10.0.0.2/32");
+        assert_eq!(proxy["ipv6"], "fd00::2/128");
+    }
+
+    #[test]
+    fn vmess_alter_id_must_be_numeric() {
+        let mut vmess = Protocol::Vmess {
+            uuid: "uuid".to_string(),
+            host: "example.com".to_string(),
+            port: 443,
+            alter_id: "4".to_string(),
+            security: "auto".to_string(),
+            packet_encoding: None,
+            transport: TransportConfig::Tcp,
+            tls: TlsConfig::default(),
+            remark: None,
+        };
+        assert_eq!(vmess.to_clash_proxy().unwrap()["alterId"], 4);
+
+        if let Protocol::Vmess { alter_id, .. } = &mut vmess {
+            *alter_id = "four".to_string();
+        }
+        assert!(matches!(
+            vmess.to_clash_proxy(),
+            Err(ConversionError::ParseError(_))
+        ));
+    }
+}
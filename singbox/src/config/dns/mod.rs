@@ -0,0 +1,102 @@
+use super::Listable;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Dns {
+    #[serde(default)]
+    pub servers: Vec<DnsServer>,
+    #[serde(default)]
+    pub rules: Vec<DnsRule>,
+    #[serde(rename = "final", default, skip_serializing_if = "Option::is_none")]
+    pub final_server: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strategy: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub independent_cache: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fakeip: Option<FakeIp>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+// sing-box 1.12 replaced address-style servers with typed ones; both shapes
+// are accepted so older configs still load.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum DnsServer {
+    Typed(TypedDnsServer),
+    Legacy(LegacyDnsServer),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TypedDnsServer {
+    pub tag: String,
+    #[serde(rename = "type")]
+    pub server_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_port: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain_resolver: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detour: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inet4_range: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inet6_range: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LegacyDnsServer {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    pub address: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address_resolver: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detour: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strategy: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DnsRule {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inbound: Option<Listable<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outbound: Option<Listable<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain: Option<Listable<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain_suffix: Option<Listable<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule_set: Option<Listable<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query_type: Option<Listable<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disable_cache: Option<bool>,
+    // Match fields we do not model, kept so loaded rules survive a round trip.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FakeIp {
+    pub enabled: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inet4_range: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inet6_range: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
@@ -0,0 +1,97 @@
+use super::{one_or_many, typed_entry};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Inbound {
+    Mixed(MixedInbound),
+    Tun(Box<TunInbound>),
+    #[serde(untagged)]
+    Other(Value),
+}
+
+impl<'de> Deserialize<'de> for Inbound {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        Ok(match value["type"].as_str().unwrap_or_default() {
+            "mixed" => Self::Mixed(typed_entry("inbound", value)?),
+            "tun" => Self::Tun(typed_entry("inbound", value)?),
+            _ => Self::Other(value),
+        })
+    }
+}
+
+impl Inbound {
+    pub fn tag(&self) -> Option<&str> {
+        match self {
+            Self::Mixed(i) => Some(&i.tag),
+            Self::Tun(i) => Some(&i.tag),
+            Self::Other(i) => i["tag"].as_str(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MixedInbound {
+    pub tag: String,
+    pub listen: String,
+    pub listen_port: u16,
+    // Fields we do not model, kept so a loaded config is written back unchanged.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TunInbound {
+    pub tag: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interface_name: Option<String>,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub address: Vec<String>,
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub route_exclude_address: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gso: Option<bool>,
+    #[serde(default)]
+    pub auto_route: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mtu: Option<u32>,
+    #[serde(default)]
+    pub strict_route: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub udp_timeout: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stack: Option<String>,
+    // Linux only; redirects TCP with nftables instead of the TUN stack.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_redirect: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub platform: Option<TunPlatform>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+// Settings applied by the sing-box apps rather than by sing-box itself.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TunPlatform {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_proxy: Option<TunHttpProxy>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+// The system HTTP proxy the app sets while the VPN is up, so apps that ignore
+// the TUN routes still go through sing-box.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TunHttpProxy {
+    pub enabled: bool,
+    pub server: String,
+    pub server_port: u16,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
@@ -0,0 +1,1050 @@
+pub mod dns;
+pub mod inbound;
+pub mod outbound;
+pub mod platform;
+pub mod route;
+pub mod snippet;
+use crate::error::ConversionError;
+use crate::protocol::{ConfigType, Protocol};
+use dns::{Dns, DnsRule, DnsServer, FakeIp, LegacyDnsServer, TypedDnsServer};
+use inbound::{Inbound, MixedInbound, TunInbound};
+use inbound::{TunHttpProxy, TunPlatform};
+use outbound::{DirectOutbound, Endpoint, Outbound};
+use platform::Platform;
+use route::{DomainResolver, Route, RouteRule, RuleSet};
+use semver::Version;
+use serde::de::{self, DeserializeOwned};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+// A single value or a list of them; sing-box accepts both for most match fields.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Listable<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+impl<T> From<T> for Listable<T> {
+    fn from(value: T) -> Self {
+        Self::One(value)
+    }
+}
+
+impl<T> From<Vec<T>> for Listable<T> {
+    fn from(values: Vec<T>) -> Self {
+        Self::Many(values)
+    }
+}
+
+impl From<&str> for Listable<String> {
+    fn from(value: &str) -> Self {
+        Self::One(value.to_string())
+    }
+}
+
+impl From<Vec<&str>> for Listable<String> {
+    fn from(values: Vec<&str>) -> Self {
+        Self::Many(values.into_iter().map(String::from).collect())
+    }
+}
+
+// Reads a `Listable` field into a plain list.
+pub(crate) fn one_or_many<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<String>, D::Error> {
+    Ok(match Listable::<String>::deserialize(deserializer)? {
+        Listable::One(value) => vec![value],
+        Listable::Many(values) => values,
+    })
+}
+
+// Reads an inbound, outbound or endpoint of a type we model. A known `type`
+// that does not fit its struct is an error, so a typo such as a quoted
+// `server_port` is reported instead of passing through untyped.
+pub(crate) fn typed_entry<T: DeserializeOwned, E: de::Error>(
+    section: &str,
+    mut value: Value,
+) -> Result<T, E> {
+    let kind = value["type"].as_str().unwrap_or_default().to_string();
+    let tag = value["tag"].as_str().unwrap_or_default().to_string();
+    if let Some(object) = value.as_object_mut() {
+        object.remove("type");
+    }
+    serde_json::from_value(value)
+        .map_err(|e| E::custom(format!("{} {} \"{}\": {}", kind, section, tag, e)))
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Log {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disabled: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<bool>,
+    // Fields we do not model, kept so a loaded config is written back unchanged.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Experimental {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_file: Option<CacheFile>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clash_api: Option<ClashApi>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CacheFile {
+    pub enabled: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ClashApi {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_controller: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_ui: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_mode: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+// How `SingBoxConfig::write_to` lays out the JSON. `Canonical` is compact with
+// every object's keys sorted, so equal configs always give identical bytes;
+// array order is kept, as it is meaningful to sing-box.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Pretty,
+    Compact,
+    Canonical,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SingBoxConfig {
+    version: Version,
+    platform: Platform,
+    log: Log,
+    dns: Dns,
+    endpoints: Vec<Endpoint>,
+    inbounds: Vec<Inbound>,
+    outbounds: Vec<Outbound>,
+    route: Route,
+    experimental: Experimental,
+}
+
+impl SingBoxConfig {
+    pub fn new(version: String) -> Result<Self, ConversionError> {
+        let version =
+            Version::parse(&version).map_err(|e| ConversionError::InvalidVersion(e.to_string()))?;
+
+        Ok(Self {
+            version,
+            platform: Platform::default(),
+            log: Log::default(),
+            dns: Dns::default(),
+            endpoints: Vec::new(),
+            inbounds: Vec::new(),
+            outbounds: Vec::new(),
+            route: Route::default(),
+            experimental: Experimental::default(),
+        })
+    }
+
+    pub fn from_json(content: &str, version: String) -> Result<Self, ConversionError> {
+        let sections: Sections = serde_json::from_str(content)
+            .map_err(|e| ConversionError::ParseError(e.to_string()))?;
+
+        Ok(Self {
+            log: sections.log,
+            dns: sections.dns,
+            endpoints: sections.endpoints,
+            inbounds: sections.inbounds,
+            outbounds: sections.outbounds,
+            route: sections.route,
+            experimental: sections.experimental,
+            ..Self::new(version)?
+        })
+    }
+
+    pub fn from_file(filename: &str, version: String) -> Result<Self, ConversionError> {
+        let content = fs::read_to_string(Path::new(filename))
+            .map_err(|e| ConversionError::IoError(e.to_string()))?;
+        Self::from_json(&content, version)
+    }
+
+    // Every proxy outbound and endpoint as a `Protocol`. Selectors,
+    // direct/block outbounds and types without a `Protocol` are skipped with a
+    // warning; a proxy that cannot be converted is an error.
+    pub fn protocols(&self) -> Result<Vec<Protocol>, ConversionError> {
+        let results = self
+            .outbounds
+            .iter()
+            .map(Outbound::to_protocol)
+            .chain(self.endpoints.iter().map(Endpoint::to_protocol));
+
+        let mut protocols = Vec::new();
+        for result in results {
+            match result {
+                Err(ConversionError::UnsupportedProtocol(kind)) => {
+                    log::warn!(
+                        "Skipping sing-box {} entry with no Protocol equivalent",
+                        kind
+                    );
+                }
+                result => protocols.push(result?),
+            }
+        }
+        Ok(protocols)
+    }
+
+    pub fn version(&self) -> &Version {
+        &self.version
+    }
+
+    pub fn platform(&self) -> Platform {
+        self.platform
+    }
+
+    pub fn log(&self) -> &Log {
+        &self.log
+    }
+
+    pub fn dns(&self) -> &Dns {
+        &self.dns
+    }
+
+    pub fn endpoints(&self) -> &[Endpoint] {
+        &self.endpoints
+    }
+
+    pub fn inbounds(&self) -> &[Inbound] {
+        &self.inbounds
+    }
+
+    pub fn outbounds(&self) -> &[Outbound] {
+        &self.outbounds
+    }
+
+    pub fn route(&self) -> &Route {
+        &self.route
+    }
+
+    pub fn experimental(&self) -> &Experimental {
+        &self.experimental
+    }
+
+    pub fn with_platform(mut self, platform: Platform) -> Self {
+        self.platform = platform;
+        self
+    }
+
+    pub fn with_log(mut self, log: Log) -> Self {
+        self.log = log;
+        self
+    }
+
+    pub fn with_dns(mut self, dns: Dns) -> Self {
+        self.dns = dns;
+        self
+    }
+
+    pub fn with_dns_server(mut self, server: DnsServer) -> Self {
+        self.dns.servers.push(server);
+        self
+    }
+
+    pub fn with_dns_rule(mut self, rule: DnsRule) -> Self {
+        self.dns.rules.push(rule);
+        self
+    }
+
+    pub fn with_inbound(mut self, inbound: Inbound) -> Self {
+        self.inbounds.push(inbound);
+        self
+    }
+
+    pub fn with_outbound(mut self, outbound: Outbound) -> Self {
+        self.outbounds.push(outbound);
+        self
+    }
+
+    pub fn with_endpoint(mut self, endpoint: Endpoint) -> Self {
+        self.endpoints.push(endpoint);
+        self
+    }
+
+    pub fn with_route(mut self, route: Route) -> Self {
+        self.route = route;
+        self
+    }
+
+    pub fn with_route_rule(mut self, rule: RouteRule) -> Self {
+        self.route.rules.push(rule);
+        self
+    }
+
+    pub fn with_rule_set(mut self, rule_set: RuleSet) -> Self {
+        self.route.rule_set.push(rule_set);
+        self
+    }
+
+    pub fn with_experimental(mut self, experimental: Experimental) -> Self {
+        self.experimental = experimental;
+        self
+    }
+
+    // {
+    //     "log": {
+    //       "disabled": false,
+    //       "level": "info",
+    //       "output": "box.log",
+    //       "timestamp": true
+    //     }
+    // }
+    pub fn set_log_level(&mut self, level: &str) -> &mut Self {
+        self.log = Log {
+            level: Some(level.to_string()),
+            timestamp: Some(true),
+            ..Default::default()
+        };
+        self
+    }
+
+    pub fn add_mixed_inbound(&mut self) -> &mut Self {
+        self.inbounds.push(Inbound::Mixed(MixedInbound {
+            tag: "mixed-in".to_string(),
+            listen: "::".to_string(),
+            listen_port: 2080,
+            extra: Map::new(),
+        }));
+        self
+    }
+
+    // Set up for the config's platform. On platforms with a system HTTP proxy
+    // it points at the mixed inbound, so add that first.
+    pub fn add_tun_inbound(&mut self) -> &mut Self {
+        let platform = self.platform;
+        let http_proxy = self
+            .inbounds
+            .iter()
+            .find_map(|inbound| match inbound {
+                Inbound::Mixed(mixed) => Some(TunHttpProxy {
+                    enabled: true,
+                    server: "127.0.0.1".to_string(),
+                    server_port: mixed.listen_port,
+                    extra: Map::new(),
+                }),
+                _ => None,
+            })
+            .filter(|_| platform.supports_http_proxy());
+
+        self.inbounds.push(Inbound::Tun(Box::new(TunInbound {
+            tag: "tun-in".to_string(),
+            interface_name: platform.interface_name().map(String::from),
+            address: vec![
+                "172.18.0.1/30".to_string(),
+                "fdfe:dcba:9876::1/126".to_string(),
+            ],
+            route_exclude_address: platform.excluded_ranges(),
+            gso: None,
+            auto_route: true,
+            mtu: Some(1358),
+            strict_route: platform.strict_route(),
+            udp_timeout: Some("5s".to_string()),
+            stack: Some(platform.tun_stack().to_string()),
+            auto_redirect: platform.auto_redirect().then_some(true),
+            platform: http_proxy.map(|http_proxy| TunPlatform {
+                http_proxy: Some(http_proxy),
+                extra: Map::new(),
+            }),
+            extra: Map::new(),
+        })));
+        self
+    }
+
+    pub fn add_outbound(&mut self, protocol: Protocol) -> Result<(), ConversionError> {
+        protocol.validate()?;
+        match protocol.to_singbox_outbound(&self.version)? {
+            ConfigType::Endpoint(endpoint) => {
+                self.endpoints.push(
+                    serde_json::from_value(endpoint)
+                        .map_err(|e| ConversionError::SerializationError(e.to_string()))?,
+                );
+            }
+            ConfigType::Outbound(outbound) => {
+                self.outbounds.push(
+                    serde_json::from_value(outbound)
+                        .map_err(|e| ConversionError::SerializationError(e.to_string()))?,
+                );
+                if !self
+                    .outbounds
+                    .iter()
+                    .any(|o| matches!(o, Outbound::Direct(_)))
+                {
+                    self.outbounds.push(Outbound::Direct(DirectOutbound {
+                        tag: "direct".to_string(),
+                        extra: Map::new(),
+                    }));
+                }
+            }
+        }
+        Ok(())
+    }
+    // {
+    //     "dns": {
+    //       "servers": [],
+    //       "rules": [],
+    //       "final": "",
+    //       "strategy": "",
+    //       "disable_cache": false,
+    //       "disable_expire": false,
+    //       "independent_cache": false,
+    //       "cache_capacity": 0,
+    //       "reverse_mapping": false,
+    //       "client_subnet": "",
+    //       "fakeip": {}
+    //     }
+    //   }
+
+    pub fn add_dns_server(&mut self) -> &mut Self {
+        if self.version >= Version::new(1, 12, 0) {
+            self.dns.servers.push(DnsServer::Typed(TypedDnsServer {
+                tag: "remote".to_string(),
+                server_type: "tls".to_string(),
+                server: Some("dns.adguard-dns.com".to_string()),
+                server_port: None,
+                domain_resolver: Some("local".to_string()),
+                detour: None,
+                inet4_range: None,
+                inet6_range: None,
+                extra: Map::new(),
+            }));
+            self.dns.servers.push(DnsServer::Typed(TypedDnsServer {
+                tag: "local".to_string(),
+                server_type: "tls".to_string(),
+                server: Some("1.1.1.1".to_string()),
+                server_port: None,
+                domain_resolver: None,
+                detour: None,
+                inet4_range: None,
+                inet6_range: None,
+                extra: Map::new(),
+            }));
+        } else {
+            self.dns.servers.push(DnsServer::Legacy(LegacyDnsServer {
+                tag: Some("remote".to_string()),
+                address: "tls://dns.adguard-dns.com".to_string(),
+                address_resolver: Some("dns-local".to_string()),
+                detour: Some("proxy".to_string()),
+                strategy: None,
+                extra: Map::new(),
+            }));
+            self.dns.servers.push(DnsServer::Legacy(LegacyDnsServer {
+                tag: Some("dns-local".to_string()),
+                address: "tls://1.1.1.1".to_string(),
+                address_resolver: None,
+                detour: Some("direct".to_string()),
+                strategy: None,
+                extra: Map::new(),
+            }));
+            self.dns.servers.push(DnsServer::Legacy(LegacyDnsServer {
+                tag: Some("fake".to_string()),
+                address: "fakeip".to_string(),
+                address_resolver: None,
+                detour: None,
+                strategy: None,
+                extra: Map::new(),
+            }));
+        }
+        self
+    }
+
+    pub fn add_dns_rule(&mut self) -> &mut Self {
+        if self.version < Version::new(1, 12, 0) {
+            self.dns.rules.push(DnsRule {
+                outbound: Some("any".into()),
+                server: Some("dns-local".to_string()),
+                ..Default::default()
+            });
+            self.dns.rules.push(DnsRule {
+                domain: Some(vec!["raw.githubusercontent.com", "time.apple.com"].into()),
+                server: Some("dns-local".to_string()),
+                ..Default::default()
+            });
+            self.dns.rules.push(DnsRule {
+                rule_set: Some("geosite-category-ir".into()),
+                server: Some("dns-local".to_string()),
+                ..Default::default()
+            });
+            self.dns.rules.push(DnsRule {
+                disable_cache: Some(true),
+                inbound: Some("tun-in".into()),
+                query_type: Some(vec!["A", "AAAA"].into()),
+                server: Some("fake".to_string()),
+                ..Default::default()
+            });
+            self.dns.independent_cache = Some(true);
+            self.dns.fakeip = Some(FakeIp {
+                enabled: true,
+                inet4_range: Some("198.18.0.0/15".to_string()),
+                inet6_range: Some("fc00::/18".to_string()),
+                extra: Map::new(),
+            });
+            self.dns.strategy = Some("prefer_ipv4".to_string());
+        }
+        self
+    }
+
+    // {
+    //     "route": {
+    //       "rules": [],
+    //       "rule_set": [],
+    //       "final": "",
+    //       "auto_detect_interface": false,
+    //       "override_android_vpn": false,
+    //       "default_interface": "",
+    //       "default_mark": 0,
+    //       "default_domain_resolver": "", // or {}
+    //       "default_network_strategy": "",
+    //       "default_network_type": [],
+    //       "default_fallback_network_type": [],
+    //       "default_fallback_delay": "",
+
+    //       // Removed
+
+    //       "geoip": {},
+    //       "geosite": {}
+    //     }
+    //   }
+    pub fn set_route(&mut self) -> &mut Self {
+        let default_domain_resolver = if self.version >= Version::new(1, 12, 0) {
+            Some(DomainResolver::Options {
+                server: "local".to_string(),
+            })
+        } else {
+            None
+        };
+
+        self.route = Route {
+            auto_detect_interface: Some(true),
+            override_android_vpn: (self.platform == Platform::Android).then_some(true),
+            default_domain_resolver,
+            rules: vec![
+                RouteRule {
+                    inbound: Some("tun-in".into()),
+                    action: Some("sniff".to_string()),
+                    ..Default::default()
+                },
+                RouteRule {
+                    protocol: Some("dns".into()),
+                    action: Some("hijack-dns".to_string()),
+                    ..Default::default()
+                },
+                RouteRule {
+                    ip_is_private: Some(true),
+                    outbound: Some("direct".to_string()),
+                    ..Default::default()
+                },
+                RouteRule {
+                    rule_set: Some(
+                        vec![
+                            "geosite-category-public-tracker",
+                            "geosite-category-ads",
+                            "geosite-category-ads-all",
+                            "geosite-google-ads",
+                        ]
+                        .into(),
+                    ),
+                    action: Some("reject".to_string()),
+                    method: Some("default".to_string()),
+                    ..Default::default()
+                },
+                RouteRule {
+                    rule_set: Some(vec!["geosite-category-ir", "geoip-ir"].into()),
+                    outbound: Some("direct".to_string()),
+                    ..Default::default()
+                },
+                // QUIC is rejected ahead of the catch-all so browsers fall back
+                // to TCP through the proxy. There is no `block` outbound, and
+                // only `tun-in` is sniffed, so the rule matches UDP/443 as Clash does.
+                RouteRule {
+                    network: Some("udp".into()),
+                    port: Some(443.into()),
+                    action: Some("reject".to_string()),
+                    ..Default::default()
+                },
+                RouteRule {
+                    inbound: Some(vec!["mixed-in", "tun-in"].into()),
+                    outbound: Some("proxy".to_string()),
+                    ..Default::default()
+                },
+            ],
+            rule_set: vec![
+                RuleSet::remote(
+                    "geosite-category-ads-all",
+                    "https://raw.githubusercontent.com/SagerNet/sing-geosite/rule-set/geosite-category-ads-all.srs",
+                    "1d",
+                ),
+                RuleSet::remote(
+                    "geosite-google-ads",
+                    "https://raw.githubusercontent.com/SagerNet/sing-geosite/rule-set/geosite-google-ads.srs",
+                    "1d",
+                ),
+                RuleSet::remote(
+                    "geosite-category-ads",
+                    "https://raw.githubusercontent.com/SagerNet/sing-geosite/rule-set/geosite-category-ads.srs",
+                    "1d",
+                ),
+                RuleSet::remote(
+                    "geosite-category-public-tracker",
+                    "https://raw.githubusercontent.com/SagerNet/sing-geosite/rule-set/geosite-category-public-tracker.srs",
+                    "1d",
+                ),
+                RuleSet::remote(
+                    "geosite-category-ir",
+                    "https://raw.githubusercontent.com/SagerNet/sing-geosite/rule-set/geosite-category-ir.srs",
+                    "168h0m0s",
+                ),
+                RuleSet::remote(
+                    "geoip-ir",
+                    "https://raw.githubusercontent.com/SagerNet/sing-geoip/rule-set/geoip-ir.srs",
+                    "168h0m0s",
+                ),
+            ],
+            ..Default::default()
+        };
+        self
+    }
+
+    pub fn add_default_experimental(&mut self) -> &mut Self {
+        self.experimental = Experimental {
+            cache_file: Some(CacheFile {
+                enabled: true,
+                path: None,
+                extra: Map::new(),
+            }),
+            ..Default::default()
+        };
+        self
+    }
+
+    pub fn to_value(&self) -> Result<Value, ConversionError> {
+        serde_json::to_value(self).map_err(|e| ConversionError::SerializationError(e.to_string()))
+    }
+
+    pub fn to_string(&self, format: OutputFormat) -> Result<String, ConversionError> {
+        let mut content = Vec::new();
+        self.write_to(&mut content, format)?;
+        String::from_utf8(content).map_err(|e| ConversionError::SerializationError(e.to_string()))
+    }
+
+    pub fn write_to(
+        &self,
+        writer: impl io::Write,
+        format: OutputFormat,
+    ) -> Result<(), ConversionError> {
+        let result = match format {
+            OutputFormat::Pretty => serde_json::to_writer_pretty(writer, self),
+            OutputFormat::Compact => serde_json::to_writer(writer, self),
+            OutputFormat::Canonical => {
+                let mut value = self.to_value()?;
+                value.sort_all_objects();
+                serde_json::to_writer(writer, &value)
+            }
+        };
+        result.map_err(|e| match e.io_error_kind() {
+            Some(_) => ConversionError::IoError(e.to_string()),
+            None => ConversionError::SerializationError(e.to_string()),
+        })
+    }
+
+    pub fn save_to_file(&self, filename: &str) -> Result<(), ConversionError> {
+        let file = fs::File::create(Path::new(filename))
+            .map_err(|e| ConversionError::IoError(e.to_string()))?;
+        let mut writer = io::BufWriter::new(file);
+        self.write_to(&mut writer, OutputFormat::Pretty)?;
+        writer
+            .flush()
+            .map_err(|e| ConversionError::IoError(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+// The top-level sections of a sing-box config file, as read by `from_json`.
+#[derive(Deserialize)]
+struct Sections {
+    #[serde(default)]
+    log: Log,
+    #[serde(default)]
+    dns: Dns,
+    #[serde(default)]
+    endpoints: Vec<Endpoint>,
+    #[serde(default)]
+    inbounds: Vec<Inbound>,
+    #[serde(default)]
+    outbounds: Vec<Outbound>,
+    #[serde(default)]
+    route: Route,
+    #[serde(default)]
+    experimental: Experimental,
+}
+
+// Sections and fields are picked by the target version: endpoints only exist
+// from 1.11 (the release that introduced them, and the one from which
+// `to_singbox_outbound` emits WireGuard as an endpoint), and
+// `default_domain_resolver` from 1.12.
+impl Serialize for SingBoxConfig {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("log", &self.log)?;
+        map.serialize_entry("dns", &self.dns)?;
+        if self.version >= Version::new(1, 11, 0) {
+            map.serialize_entry("endpoints", &self.endpoints)?;
+        }
+        map.serialize_entry("inbounds", &self.inbounds)?;
+        map.serialize_entry("outbounds", &self.outbounds)?;
+        if self.version < Version::new(1, 12, 0) && self.route.default_domain_resolver.is_some() {
+            let mut route = self.route.clone();
+            route.default_domain_resolver = None;
+            map.serialize_entry("route", &route)?;
+        } else {
+            map.serialize_entry("route", &self.route)?;
+        }
+        map.serialize_entry("experimental", &self.experimental)?;
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::transport::TransportConfig;
+    use serde_json::json;
+
+    fn load(config: Value) -> SingBoxConfig {
+        SingBoxConfig::from_json(&config.to_string(), "1.11.0".to_string()).unwrap()
+    }
+
+    fn full_config(version: &str) -> SingBoxConfig {
+        let mut config = SingBoxConfig::new(version.to_string()).unwrap();
+        config
+            .set_log_level("info")
+            .add_dns_server()
+            .add_mixed_inbound()
+            .add_tun_inbound()
+            .set_route()
+            .add_default_experimental();
+        config
+            .add_outbound(
+                Protocol::parse_uri(
+                    "trojan://secret@example.com:443?security=tls&type=ws&path=/ws",
+                )
+                .unwrap(),
+            )
+            .unwrap();
+        config
+    }
+
+    fn assert_sorted(value: &Value) {
+        match value {
+            Value::Object(map) => {
+                let keys: Vec<_> = map.keys().collect();
+                let mut sorted = keys.clone();
+                sorted.sort();
+                assert_eq!(keys, sorted);
+                map.values().for_each(assert_sorted);
+            }
+            Value::Array(values) => values.iter().for_each(assert_sorted),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn canonical_output_is_stable_and_key_sorted() {
+        let config = full_config("1.12.0");
+        let canonical = config.to_string(OutputFormat::Canonical).unwrap();
+        assert!(!canonical.contains('\n'));
+        assert_sorted(&serde_json::from_str(&canonical).unwrap());
+
+        // Loading the pretty form back, or loading JSON with its keys in
+        // another order, gives the same bytes.
+        let pretty = config.to_string(OutputFormat::Pretty).unwrap();
+        let reloaded = SingBoxConfig::from_json(&pretty, "1.12.0".to_string()).unwrap();
+        assert_eq!(
+            reloaded.to_string(OutputFormat::Canonical).unwrap(),
+            canonical
+        );
+
+        let mut reversed = Map::new();
+        let value = config.to_value().unwrap();
+        for (key, section) in value.as_object().unwrap().iter().rev() {
+            reversed.insert(key.clone(), section.clone());
+        }
+        let reordered =
+            SingBoxConfig::from_json(&Value::Object(reversed).to_string(), "1.12.0".to_string())
+                .unwrap();
+        assert_eq!(
+            reordered.to_string(OutputFormat::Canonical).unwrap(),
+            canonical
+        );
+
+        // Arrays keep their order: rules are matched top to bottom.
+        let canonical: Value = serde_json::from_str(&canonical).unwrap();
+        assert_eq!(canonical["route"]["rules"], value["route"]["rules"]);
+        assert_eq!(canonical, value);
+    }
+
+    #[test]
+    fn each_platform_gets_its_tun_profile() {
+        // (platform, stack, interface_name, strict_route, auto_redirect,
+        //  system HTTP proxy, override_android_vpn)
+        let expected = [
+            (
+                Platform::Android,
+                "mixed",
+                None,
+                true,
+                None,
+                true,
+                Some(true),
+            ),
+            (Platform::Ios, "gvisor", None, false, None, true, None),
+            (
+                Platform::Windows,
+                "gvisor",
+                Some("sing-box"),
+                true,
+                None,
+                false,
+                None,
+            ),
+            (Platform::MacOs, "mixed", None, false, None, true, None),
+            (
+                Platform::LinuxDesktop,
+                "system",
+                Some("tun0"),
+                true,
+                None,
+                false,
+                None,
+            ),
+            (
+                Platform::LinuxRouter,
+                "system",
+                Some("tun0"),
+                true,
+                Some(true),
+                false,
+                None,
+            ),
+        ];
+        assert_eq!(expected.len(), platform::PLATFORMS.len());
+
+        for (platform, stack, interface_name, strict_route, auto_redirect, http_proxy, android) in
+            expected
+        {
+            let config = full_config("1.11.0").with_platform(platform);
+            let mut config = SingBoxConfig {
+                inbounds: Vec::new(),
+                ..config
+            };
+            config.add_mixed_inbound().add_tun_inbound().set_route();
+            let value = config.to_value().unwrap();
+            let tun = &value["inbounds"][1];
+
+            assert_eq!(tun["stack"], stack, "{}", platform);
+            assert_eq!(
+                tun["interface_name"].as_str(),
+                interface_name,
+                "{}",
+                platform
+            );
+            assert_eq!(tun["strict_route"], strict_route, "{}", platform);
+            assert_eq!(
+                tun["auto_redirect"].as_bool(),
+                auto_redirect,
+                "{}",
+                platform
+            );
+            assert!(tun.get("gso").is_none(), "{}", platform);
+            assert_eq!(
+                tun["platform"]["http_proxy"]["server_port"].as_u64(),
+                http_proxy.then_some(2080),
+                "{}",
+                platform
+            );
+            assert_eq!(
+                value["route"]["override_android_vpn"].as_bool(),
+                android,
+                "{}",
+                platform
+            );
+            let multicast = tun["route_exclude_address"]
+                .as_array()
+                .unwrap()
+                .contains(&json!("224.0.0.0/4"));
+            assert_eq!(multicast, platform == Platform::LinuxRouter, "{}", platform);
+        }
+        assert_eq!(full_config("1.11.0").platform(), Platform::Android);
+    }
+
+    #[test]
+    fn quic_is_rejected_before_the_proxy_catch_all() {
+        let mut config = SingBoxConfig::new("1.11.0".to_string()).unwrap();
+        config.set_route();
+        let rules = &config.route().rules;
+        let catch_all = rules
+            .iter()
+            .position(|rule| rule.outbound.as_deref() == Some("proxy"))
+            .unwrap();
+        let quic = rules
+            .iter()
+            .position(|rule| rule.port == Some(443.into()))
+            .unwrap();
+        assert!(quic < catch_all);
+        assert_eq!(rules[quic].action.as_deref(), Some("reject"));
+    }
+
+    #[test]
+    fn unmodelled_fields_survive_a_round_trip() {
+        let config = json!({
+            "log": { "level": "info", "disabled": false },
+            "dns": {
+                "servers": [{ "tag": "remote", "address": "tls://1.1.1.1", "client_subnet": "1.0.0.0/24" }],
+                "rules": [],
+                "client_subnet": "1.0.0.0/24"
+            },
+            "endpoints": [],
+            "inbounds": [{
+                "type": "tun",
+                "tag": "tun-in",
+                "address": "172.18.0.1/30",
+                "auto_route": true,
+                "strict_route": false,
+                "inet4_route_address": ["0.0.0.0/1"],
+                "sniff": true
+            }],
+            "outbounds": [{
+                "type": "trojan",
+                "tag": "proxy",
+                "server": "example.com",
+                "server_port": 443,
+                "password": "secret",
+                "detour": "upstream",
+                "multiplex": { "enabled": true, "protocol": "h2mux" }
+            }],
+            "route": { "rules": [], "rule_set": [], "default_mark": 233 },
+            "experimental": {
+                "clash_api": {
+                    "external_controller": "127.0.0.1:9090",
+                    "access_control_allow_origin": ["*"]
+                }
+            }
+        });
+        let loaded = load(config.clone());
+        let mut written = loaded.to_value().unwrap();
+        let mut expected = config;
+        // `address` is written back as the list sing-box also accepts.
+        expected["inbounds"][0]["address"] = json!(["172.18.0.1/30"]);
+        written.sort_all_objects();
+        expected.sort_all_objects();
+        assert_eq!(written, expected);
+    }
+
+    #[test]
+    fn list_valued_headers_are_accepted() {
+        let config = load(json!({
+            "outbounds": [{
+                "type": "trojan",
+                "tag": "proxy",
+                "server": "example.com",
+                "server_port": 443,
+                "password": "secret",
+                "transport": {
+                    "type": "ws",
+                    "path": "/ws",
+                    "headers": { "Host": ["cdn.example.com"], "X-Forwarded-For": ["a", "b"] }
+                }
+            }]
+        }));
+        let protocols = config.protocols().unwrap();
+        assert_eq!(protocols.len(), 1);
+        match &protocols[0] {
+            Protocol::Trojan {
+                transport: TransportConfig::Websocket { headers, .. },
+                ..
+            } => {
+                assert_eq!(headers["Host"], "cdn.example.com");
+                assert_eq!(headers["X-Forwarded-For"], "a, b");
+            }
+            other => panic!("expected trojan over ws, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn protocols_skips_non_proxies_and_reports_broken_nodes() {
+        let config = load(json!({
+            "outbounds": [
+                { "type": "direct", "tag": "direct" },
+                { "type": "socks", "tag": "socks", "server": "127.0.0.1", "server_port": 1080 },
+                {
+                    "type": "shadowsocks",
+                    "tag": "ss",
+                    "server": "example.com",
+                    "server_port": 8388,
+                    "method": "aes-128-gcm",
+                    "password": "secret"
+                }
+            ]
+        }));
+        assert_eq!(config.protocols().unwrap().len(), 1);
+
+        let config = load(json!({
+            "endpoints": [{
+                "type": "wireguard",
+                "tag": "wg",
+                "address": [],
+                "private_key": "key",
+                "peers": [{ "address": "example.com", "port": 51820, "public_key": "pub" }]
+            }]
+        }));
+        assert!(matches!(
+            config.protocols(),
+            Err(ConversionError::MissingIP)
+        ));
+    }
+
+    #[test]
+    fn malformed_known_outbound_fails_to_load() {
+        let config = json!({
+            "outbounds": [{
+                "type": "vmess",
+                "tag": "proxy",
+                "server": "example.com",
+                "server_port": "443",
+                "uuid": "uuid"
+            }]
+        });
+        assert!(SingBoxConfig::from_json(&config.to_string(), "1.11.0".to_string()).is_err());
+    }
+}
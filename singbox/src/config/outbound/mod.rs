@@ -0,0 +1,744 @@
+use super::{one_or_many, typed_entry, Listable};
+use crate::error::ConversionError;
+use crate::protocol::tls::{RealityConfig, TlsConfig, UTlsConfig};
+use crate::protocol::transport::TransportConfig;
+use crate::protocol::Protocol;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Outbound {
+    Direct(DirectOutbound),
+    Block(BlockOutbound),
+    Dns(DnsOutbound),
+    Selector(SelectorOutbound),
+    UrlTest(UrlTestOutbound),
+    Shadowsocks(ShadowsocksOutbound),
+    Vmess(VmessOutbound),
+    Vless(VlessOutbound),
+    Trojan(TrojanOutbound),
+    Wireguard(WireguardOutbound),
+    Hysteria2(Hysteria2Outbound),
+    Tuic(TuicOutbound),
+    // Outbound types we do not model are carried through untouched.
+    #[serde(untagged)]
+    Other(Value),
+}
+
+impl<'de> Deserialize<'de> for Outbound {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        Ok(match value["type"].as_str().unwrap_or_default() {
+            "direct" => Self::Direct(typed_entry("outbound", value)?),
+            "block" => Self::Block(typed_entry("outbound", value)?),
+            "dns" => Self::Dns(typed_entry("outbound", value)?),
+            "selector" => Self::Selector(typed_entry("outbound", value)?),
+            "urltest" => Self::UrlTest(typed_entry("outbound", value)?),
+            "shadowsocks" => Self::Shadowsocks(typed_entry("outbound", value)?),
+            "vmess" => Self::Vmess(typed_entry("outbound", value)?),
+            "vless" => Self::Vless(typed_entry("outbound", value)?),
+            "trojan" => Self::Trojan(typed_entry("outbound", value)?),
+            "wireguard" => Self::Wireguard(typed_entry("outbound", value)?),
+            "hysteria2" => Self::Hysteria2(typed_entry("outbound", value)?),
+            "tuic" => Self::Tuic(typed_entry("outbound", value)?),
+            _ => Self::Other(value),
+        })
+    }
+}
+
+impl Outbound {
+    pub fn tag(&self) -> Option<&str> {
+        match self {
+            Self::Direct(o) => Some(&o.tag),
+            Self::Block(o) => Some(&o.tag),
+            Self::Dns(o) => Some(&o.tag),
+            Self::Selector(o) => Some(&o.tag),
+            Self::UrlTest(o) => Some(&o.tag),
+            Self::Shadowsocks(o) => Some(&o.tag),
+            Self::Vmess(o) => Some(&o.tag),
+            Self::Vless(o) => Some(&o.tag),
+            Self::Trojan(o) => Some(&o.tag),
+            Self::Wireguard(o) => Some(&o.tag),
+            Self::Hysteria2(o) => Some(&o.tag),
+            Self::Tuic(o) => Some(&o.tag),
+            Self::Other(o) => o["tag"].as_str(),
+        }
+    }
+
+    // Turns a proxy outbound back into a `Protocol`, keeping its tag as the remark.
+    pub fn to_protocol(&self) -> Result<Protocol, ConversionError> {
+        match self {
+            Self::Shadowsocks(o) => Ok(Protocol::Shadowsocks {
+                method: o.method.clone(),
+                password: o.password.clone(),
+                host: o.server.clone(),
+                port: o.server_port,
+                plugin: o.plugin.clone(),
+                plugin_opts: o.plugin_opts.clone(),
+                remark: Some(o.tag.clone()),
+            }),
+            Self::Vmess(o) => Ok(Protocol::Vmess {
+                uuid: o.uuid.clone(),
+                host: o.server.clone(),
+                port: o.server_port,
+                alter_id: o.alter_id.unwrap_or(0).to_string(),
+                security: o.security.clone().unwrap_or_else(|| "auto".to_string()),
+                packet_encoding: o.packet_encoding.clone().filter(|p| !p.is_empty()),
+                transport: to_transport_config(&o.transport),
+                tls: to_tls_config(&o.tls),
+                remark: Some(o.tag.clone()),
+            }),
+            Self::Vless(o) => Ok(Protocol::Vless {
+                uuid: o.uuid.clone(),
+                host: o.server.clone(),
+                port: o.server_port,
+                flow: o.flow.clone().filter(|f| !f.is_empty()),
+                packet_encoding: o.packet_encoding.clone().filter(|p| !p.is_empty()),
+                transport: to_transport_config(&o.transport),
+                tls: to_tls_config(&o.tls),
+                remark: Some(o.tag.clone()),
+            }),
+            Self::Trojan(o) => Ok(Protocol::Trojan {
+                password: o.password.clone(),
+                host: o.server.clone(),
+                port: o.server_port,
+                transport: to_transport_config(&o.transport),
+                tls: to_tls_config(&o.tls),
+                remark: Some(o.tag.clone()),
+            }),
+            Self::Wireguard(o) => Ok(Protocol::Wireguard {
+                private_key: o.private_key.clone(),
+                public_key: o.peer_public_key.clone(),
+                endpoint: join_endpoint(&o.server, o.server_port),
+                dns: None,
+                mtu: o.mtu,
+                ip: Some(o.local_address.join(","))
+                    .filter(|ip| !ip.is_empty())
+                    .ok_or(ConversionError::MissingIP)?,
+                pre_shared_key: o.pre_shared_key.clone(),
+                persistent_keepalive: None,
+                amnezia: None,
+                remark: Some(o.tag.clone()),
+            }),
+            Self::Hysteria2(o) => Ok(Protocol::Hysteria2 {
+                password: o.password.clone(),
+                host: o.server.clone(),
+                port: o.server_port,
+                obfs: o.obfs.as_ref().map(|obfs| obfs.obfs_type.clone()),
+                obfs_password: o.obfs.as_ref().and_then(|obfs| obfs.password.clone()),
+                up_mbps: o.up_mbps,
+                down_mbps: o.down_mbps,
+                tls: to_tls_config(&o.tls),
+                remark: Some(o.tag.clone()),
+            }),
+            Self::Tuic(o) => Ok(Protocol::Tuic {
+                uuid: o.uuid.clone(),
+                password: o.password.clone(),
+                host: o.server.clone(),
+                port: o.server_port,
+                congestion_control: o.congestion_control.clone(),
+                udp_relay_mode: o.udp_relay_mode.clone(),
+                tls: to_tls_config(&o.tls),
+                remark: Some(o.tag.clone()),
+            }),
+            Self::Direct(_) => Err(ConversionError::UnsupportedProtocol("direct".to_string())),
+            Self::Block(_) => Err(ConversionError::UnsupportedProtocol("block".to_string())),
+            Self::Dns(_) => Err(ConversionError::UnsupportedProtocol("dns".to_string())),
+            Self::Selector(_) => Err(ConversionError::UnsupportedProtocol("selector".to_string())),
+            Self::UrlTest(_) => Err(ConversionError::UnsupportedProtocol("urltest".to_string())),
+            Self::Other(o) => Err(ConversionError::UnsupportedProtocol(
+                o["type"].as_str().unwrap_or_default().to_string(),
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DirectOutbound {
+    pub tag: String,
+    // Fields we do not model, kept so a loaded config is written back unchanged.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockOutbound {
+    pub tag: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DnsOutbound {
+    pub tag: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SelectorOutbound {
+    pub tag: String,
+    pub outbounds: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interrupt_exist_connections: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UrlTestOutbound {
+    pub tag: String,
+    pub outbounds: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tolerance: Option<u16>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShadowsocksOutbound {
+    pub tag: String,
+    pub server: String,
+    pub server_port: u16,
+    pub method: String,
+    pub password: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plugin: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plugin_opts: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VmessOutbound {
+    pub tag: String,
+    pub server: String,
+    pub server_port: u16,
+    pub uuid: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub security: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alter_id: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub packet_encoding: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<OutboundTls>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transport: Option<V2RayTransport>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VlessOutbound {
+    pub tag: String,
+    pub server: String,
+    pub server_port: u16,
+    pub uuid: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flow: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub packet_encoding: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<OutboundTls>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transport: Option<V2RayTransport>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrojanOutbound {
+    pub tag: String,
+    pub server: String,
+    pub server_port: u16,
+    pub password: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<OutboundTls>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transport: Option<V2RayTransport>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+// The pre-1.11 WireGuard outbound; newer versions use `WireguardEndpoint`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WireguardOutbound {
+    pub tag: String,
+    pub server: String,
+    pub server_port: u16,
+    #[serde(deserialize_with = "one_or_many")]
+    pub local_address: Vec<String>,
+    pub private_key: String,
+    pub peer_public_key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre_shared_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mtu: Option<u16>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Hysteria2Outbound {
+    pub tag: String,
+    pub server: String,
+    pub server_port: u16,
+    pub password: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub up_mbps: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub down_mbps: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub obfs: Option<Hysteria2Obfs>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<OutboundTls>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Hysteria2Obfs {
+    #[serde(rename = "type")]
+    pub obfs_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TuicOutbound {
+    pub tag: String,
+    pub server: String,
+    pub server_port: u16,
+    pub uuid: String,
+    #[serde(default)]
+    pub password: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub congestion_control: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub udp_relay_mode: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<OutboundTls>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Endpoint {
+    Wireguard(WireguardEndpoint),
+    #[serde(untagged)]
+    Other(Value),
+}
+
+impl<'de> Deserialize<'de> for Endpoint {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        Ok(match value["type"].as_str().unwrap_or_default() {
+            "wireguard" => Self::Wireguard(typed_entry("endpoint", value)?),
+            _ => Self::Other(value),
+        })
+    }
+}
+
+impl Endpoint {
+    pub fn tag(&self) -> Option<&str> {
+        match self {
+            Self::Wireguard(e) => Some(&e.tag),
+            Self::Other(e) => e["tag"].as_str(),
+        }
+    }
+
+    pub fn to_protocol(&self) -> Result<Protocol, ConversionError> {
+        match self {
+            Self::Wireguard(e) => {
+                // A node has room for a single peer; keeping only the first
+                // would route the others' traffic through it.
+                let peer = match e.peers.as_slice() {
+                    [peer] => peer,
+                    [] => return Err(ConversionError::MissingPublicKey),
+                    peers => {
+                        return Err(ConversionError::UnsupportedFeature(format!(
+                            "WireGuard endpoint {} has {} peers; only one can be converted",
+                            e.tag,
+                            peers.len()
+                        )))
+                    }
+                };
+                Ok(Protocol::Wireguard {
+                    private_key: e.private_key.clone(),
+                    public_key: peer.public_key.clone(),
+                    endpoint: join_endpoint(&peer.address, peer.port),
+                    dns: None,
+                    mtu: e.mtu,
+                    ip: Some(e.address.join(","))
+                        .filter(|ip| !ip.is_empty())
+                        .ok_or(ConversionError::MissingIP)?,
+                    pre_shared_key: peer.pre_shared_key.clone(),
+                    persistent_keepalive: peer.persistent_keepalive_interval,
+                    amnezia: None,
+                    remark: Some(e.tag.clone()),
+                })
+            }
+            Self::Other(e) => Err(ConversionError::UnsupportedProtocol(
+                e["type"].as_str().unwrap_or_default().to_string(),
+            )),
+        }
+    }
+}
+
+fn join_endpoint(host: &str, port: u16) -> String {
+    if host.contains(':') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
+
+fn to_transport_config(transport: &Option<V2RayTransport>) -> TransportConfig {
+    match transport {
+        None => TransportConfig::Tcp,
+        Some(V2RayTransport::Http {
+            host,
+            path,
+            method,
+            headers,
+            idle_timeout,
+            ping_timeout,
+            ..
+        }) => TransportConfig::Http {
+            host: host.clone(),
+            path: path.clone(),
+            method: method.clone(),
+            headers: join_headers(headers),
+            idle_timeout: idle_timeout.clone(),
+            ping_timeout: ping_timeout.clone(),
+        },
+        Some(V2RayTransport::Ws {
+            path,
+            headers,
+            max_early_data,
+            early_data_header_name,
+            ..
+        }) => TransportConfig::Websocket {
+            path: path.clone(),
+            headers: join_headers(headers),
+            max_early_data: *max_early_data,
+            early_data_header_name: early_data_header_name.clone(),
+        },
+        Some(V2RayTransport::Quic { .. }) => TransportConfig::Quic {
+            security: "none".to_string(),
+            key: String::new(),
+            header_type: "none".to_string(),
+        },
+        Some(V2RayTransport::Grpc {
+            service_name,
+            idle_timeout,
+            ping_timeout,
+            permit_without_stream,
+            ..
+        }) => TransportConfig::Grpc {
+            service_name: service_name.clone(),
+            idle_timeout: idle_timeout.clone(),
+            ping_timeout: ping_timeout.clone(),
+            permit_without_stream: *permit_without_stream,
+        },
+        Some(V2RayTransport::Httpupgrade {
+            host,
+            path,
+            headers,
+            ..
+        }) => TransportConfig::Httpupgrade {
+            host: host.clone(),
+            path: path.clone(),
+            headers: join_headers(headers),
+        },
+    }
+}
+
+// sing-box allows several values per header; they are joined the way HTTP
+// combines repeated headers.
+fn join_headers(headers: &HashMap<String, Listable<String>>) -> HashMap<String, String> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = match value {
+                Listable::One(value) => value.clone(),
+                Listable::Many(values) => values.join(", "),
+            };
+            (name.clone(), value)
+        })
+        .collect()
+}
+
+fn to_tls_config(tls: &Option<OutboundTls>) -> TlsConfig {
+    let Some(tls) = tls else {
+        return TlsConfig::default();
+    };
+    TlsConfig {
+        enabled: tls.enabled,
+        insecure: tls.insecure,
+        sni: tls.server_name.clone().filter(|s| !s.is_empty()),
+        alpn: tls.alpn.clone(),
+        utls: tls.utls.as_ref().map(|utls| UTlsConfig {
+            enabled: utls.enabled,
+            fingerprint: utls.fingerprint.clone(),
+        }),
+        reality: tls
+            .reality
+            .as_ref()
+            .filter(|reality| reality.enabled)
+            .map(|reality| RealityConfig {
+                public_key: reality.public_key.clone(),
+                short_id: reality.short_id.clone(),
+            }),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WireguardEndpoint {
+    pub tag: String,
+    #[serde(deserialize_with = "one_or_many")]
+    pub address: Vec<String>,
+    pub private_key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mtu: Option<u16>,
+    pub peers: Vec<WireguardPeer>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WireguardPeer {
+    pub address: String,
+    pub port: u16,
+    pub public_key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre_shared_key: Option<String>,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub allowed_ips: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub persistent_keepalive_interval: Option<u16>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OutboundTls {
+    pub enabled: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_name: Option<String>,
+    #[serde(default)]
+    pub insecure: bool,
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub alpn: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub utls: Option<OutboundUtls>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reality: Option<OutboundReality>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutboundUtls {
+    pub enabled: bool,
+    #[serde(default)]
+    pub fingerprint: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutboundReality {
+    pub enabled: bool,
+    pub public_key: String,
+    #[serde(default)]
+    pub short_id: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum V2RayTransport {
+    Http {
+        #[serde(default, deserialize_with = "one_or_many")]
+        host: Vec<String>,
+        #[serde(default)]
+        path: String,
+        #[serde(default)]
+        method: String,
+        #[serde(default)]
+        headers: HashMap<String, Listable<String>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        idle_timeout: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ping_timeout: Option<String>,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
+    },
+    Ws {
+        #[serde(default)]
+        path: String,
+        #[serde(default)]
+        headers: HashMap<String, Listable<String>>,
+        #[serde(default)]
+        max_early_data: u32,
+        #[serde(default)]
+        early_data_header_name: String,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
+    },
+    Quic {
+        #[serde(flatten)]
+        extra: Map<String, Value>,
+    },
+    Grpc {
+        #[serde(default)]
+        service_name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        idle_timeout: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ping_timeout: Option<String>,
+        #[serde(default)]
+        permit_without_stream: bool,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
+    },
+    Httpupgrade {
+        #[serde(default)]
+        host: String,
+        #[serde(default)]
+        path: String,
+        #[serde(default)]
+        headers: HashMap<String, Listable<String>>,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn known_type_with_wrong_field_is_an_error() {
+        let outbound = json!({
+            "type": "vmess",
+            "tag": "proxy",
+            "server": "example.com",
+            "server_port": "443",
+            "uuid": "uuid",
+        });
+        let error = serde_json::from_value::<Outbound>(outbound).unwrap_err();
+        assert!(error.to_string().contains("vmess outbound \"proxy\""));
+    }
+
+    #[test]
+    fn unknown_type_is_kept_untyped() {
+        let outbound = json!({
+            "type": "socks",
+            "tag": "socks-out",
+            "server": "127.0.0.1",
+            "server_port": 1080,
+        });
+        let parsed = serde_json::from_value::<Outbound>(outbound.clone()).unwrap();
+        assert_eq!(parsed, Outbound::Other(outbound.clone()));
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), outbound);
+    }
+
+    #[test]
+    fn known_endpoint_type_with_wrong_field_is_an_error() {
+        let endpoint = json!({
+            "type": "wireguard",
+            "tag": "wg",
+            "address": ["10.0.0.2/32"],
+            "private_key": 1,
+            "peers": [],
+        });
+        assert!(serde_json::from_value::<Endpoint>(endpoint).is_err());
+    }
+
+    fn write_back(outbound: Value) -> Value {
+        let protocol = serde_json::from_value::<Outbound>(outbound)
+            .unwrap()
+            .to_protocol()
+            .unwrap();
+        protocol
+            .to_singbox_outbound(&semver::Version::new(1, 12, 0))
+            .unwrap()
+            .into_value()
+    }
+
+    #[test]
+    fn disabled_utls_stays_disabled() {
+        let written = write_back(json!({
+            "type": "trojan",
+            "tag": "proxy",
+            "server": "example.com",
+            "server_port": 443,
+            "password": "secret",
+            "tls": {
+                "enabled": true,
+                "server_name": "example.com",
+                "utls": { "enabled": false, "fingerprint": "chrome" }
+            }
+        }));
+        assert_eq!(
+            written["tls"]["utls"],
+            json!({ "enabled": false, "fingerprint": "chrome" })
+        );
+    }
+
+    #[test]
+    fn vmess_packet_encoding_and_missing_timeouts_are_kept() {
+        let written = write_back(json!({
+            "type": "vmess",
+            "tag": "proxy",
+            "server": "example.com",
+            "server_port": 443,
+            "uuid": "uuid",
+            "packet_encoding": "xudp",
+            "transport": { "type": "grpc", "service_name": "svc" }
+        }));
+        assert_eq!(written["packet_encoding"], "xudp");
+        assert_eq!(written["transport"]["service_name"], "svc");
+        assert!(written["transport"].get("idle_timeout").is_none());
+        assert!(written["transport"].get("ping_timeout").is_none());
+    }
+
+    #[test]
+    fn endpoint_with_several_peers_is_an_error() {
+        let peer =
+            |address: &str| json!({ "address": address, "port": 51820, "public_key": "pub" });
+        let endpoint = json!({
+            "type": "wireguard",
+            "tag": "wg",
+            "address": ["10.0.0.2/32"],
+            "private_key": "key",
+            "peers": [peer("198.51.100.1"), peer("198.51.100.2")],
+        });
+        let endpoint = serde_json::from_value::<Endpoint>(endpoint).unwrap();
+        assert!(matches!(
+            endpoint.to_protocol(),
+            Err(ConversionError::UnsupportedFeature(_))
+        ));
+    }
+}
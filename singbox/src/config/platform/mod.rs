@@ -0,0 +1,143 @@
+use crate::error::ConversionError;
+use std::fmt;
+use std::str::FromStr;
+
+// The device a sing-box config is generated for. It decides how the TUN
+// inbound is set up and whether Android-only route options are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Platform {
+    // sing-box for Android (SFA). The default, as the route section already
+    // set `override_android_vpn`; the `tun0` interface name written before
+    // platforms existed is now only used on Linux.
+    #[default]
+    Android,
+    // sing-box for iOS (SFI).
+    Ios,
+    Windows,
+    // sing-box for macOS (SFM) or the command line.
+    MacOs,
+    LinuxDesktop,
+    // OpenWrt and other gateways routing traffic for a whole LAN.
+    LinuxRouter,
+}
+
+pub const PLATFORMS: [Platform; 6] = [
+    Platform::Android,
+    Platform::Ios,
+    Platform::Windows,
+    Platform::MacOs,
+    Platform::LinuxDesktop,
+    Platform::LinuxRouter,
+];
+
+impl Platform {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Android => "android",
+            Self::Ios => "ios",
+            Self::Windows => "windows",
+            Self::MacOs => "macos",
+            Self::LinuxDesktop => "linux",
+            Self::LinuxRouter => "linux-router",
+        }
+    }
+
+    // gVisor keeps the memory-limited iOS network extension and Windows,
+    // where the system stack needs firewall rules, working; Linux gets the
+    // faster system stack.
+    pub fn tun_stack(&self) -> &'static str {
+        match self {
+            Self::Android | Self::MacOs => "mixed",
+            Self::Ios | Self::Windows => "gvisor",
+            Self::LinuxDesktop | Self::LinuxRouter => "system",
+        }
+    }
+
+    // Android and iOS name the interface themselves and macOS only accepts
+    // `utunN`, so the name is left to the system there.
+    pub fn interface_name(&self) -> Option<&'static str> {
+        match self {
+            Self::Windows => Some("sing-box"),
+            Self::LinuxDesktop | Self::LinuxRouter => Some("tun0"),
+            Self::Android | Self::Ios | Self::MacOs => None,
+        }
+    }
+
+    pub fn strict_route(&self) -> bool {
+        !matches!(self, Self::Ios | Self::MacOs)
+    }
+
+    pub fn auto_redirect(&self) -> bool {
+        *self == Self::LinuxRouter
+    }
+
+    // Only the graphical sing-box apps can set a system HTTP proxy.
+    pub fn supports_http_proxy(&self) -> bool {
+        matches!(self, Self::Android | Self::Ios | Self::MacOs)
+    }
+
+    // Ranges kept off the tunnel: the LAN, and on a router also multicast so
+    // discovery protocols keep working between clients.
+    pub fn excluded_ranges(&self) -> Vec<String> {
+        let mut ranges = vec![
+            "192.168.0.0/16",
+            "10.0.0.0/8",
+            "169.254.0.0/16",
+            "172.16.0.0/12",
+            "fe80::/10",
+            "fc00::/7",
+        ];
+        if *self == Self::LinuxRouter {
+            ranges.extend(["224.0.0.0/4", "ff00::/8"]);
+        }
+        ranges.into_iter().map(String::from).collect()
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Platform {
+    type Err = ConversionError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "android" | "sfa" => Ok(Self::Android),
+            "ios" | "sfi" => Ok(Self::Ios),
+            "windows" => Ok(Self::Windows),
+            "macos" | "sfm" => Ok(Self::MacOs),
+            "linux" | "linux-desktop" => Ok(Self::LinuxDesktop),
+            "linux-router" | "router" | "openwrt" => Ok(Self::LinuxRouter),
+            _ => Err(ConversionError::UnsupportedPlatform(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_and_aliases_parse() {
+        for platform in PLATFORMS {
+            assert_eq!(platform.as_str().parse::<Platform>().unwrap(), platform);
+            assert_eq!(platform.to_string(), platform.as_str());
+        }
+        for (alias, platform) in [
+            ("SFA", Platform::Android),
+            ("sfi", Platform::Ios),
+            ("sfm", Platform::MacOs),
+            ("linux-desktop", Platform::LinuxDesktop),
+            ("OpenWrt", Platform::LinuxRouter),
+            ("router", Platform::LinuxRouter),
+        ] {
+            assert_eq!(alias.parse::<Platform>().unwrap(), platform);
+        }
+        assert!(matches!(
+            "freebsd".parse::<Platform>(),
+            Err(ConversionError::UnsupportedPlatform(_))
+        ));
+    }
+}
@@ -0,0 +1,94 @@
+use super::Listable;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Route {
+    #[serde(default)]
+    pub rules: Vec<RouteRule>,
+    #[serde(default)]
+    pub rule_set: Vec<RuleSet>,
+    #[serde(rename = "final", default, skip_serializing_if = "Option::is_none")]
+    pub final_outbound: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_detect_interface: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub override_android_vpn: Option<bool>,
+    // Only understood by sing-box 1.12 and later.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_domain_resolver: Option<DomainResolver>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum DomainResolver {
+    Tag(String),
+    Options { server: String },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RouteRule {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inbound: Option<Listable<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<Listable<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<Listable<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<Listable<u16>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip_is_private: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain: Option<Listable<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain_suffix: Option<Listable<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip_cidr: Option<Listable<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule_set: Option<Listable<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outbound: Option<String>,
+    // Match fields we do not model, kept so loaded rules survive a round trip.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuleSet {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub tag: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download_detour: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub update_interval: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl RuleSet {
+    pub fn remote(tag: &str, url: &str, update_interval: &str) -> Self {
+        Self {
+            kind: "remote".to_string(),
+            tag: tag.to_string(),
+            format: Some("binary".to_string()),
+            url: Some(url.to_string()),
+            path: None,
+            download_detour: Some("direct".to_string()),
+            update_interval: Some(update_interval.to_string()),
+            extra: Map::new(),
+        }
+    }
+}
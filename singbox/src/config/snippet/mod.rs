@@ -0,0 +1,97 @@
+use crate::error::ConversionError;
+use crate::protocol::{with_unique_names, ConfigType, Protocol};
+use semver::Version;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+
+// Just the outbounds and endpoints for some nodes, without the DNS, inbounds
+// and routing of a full config, for pasting into a hand-maintained one.
+#[derive(Debug)]
+pub struct OutboundSnippets {
+    version: Version,
+    snippets: Vec<ConfigType>,
+}
+
+impl OutboundSnippets {
+    pub fn new(version: String) -> Result<Self, ConversionError> {
+        let version =
+            Version::parse(&version).map_err(|e| ConversionError::InvalidVersion(e.to_string()))?;
+
+        Ok(Self {
+            version,
+            snippets: Vec::new(),
+        })
+    }
+
+    pub fn version(&self) -> &Version {
+        &self.version
+    }
+
+    pub fn snippets(&self) -> &[ConfigType] {
+        &self.snippets
+    }
+
+    pub fn add(&mut self, protocol: &Protocol, tag: &str) -> Result<&ConfigType, ConversionError> {
+        protocol.validate()?;
+        let mut snippet = protocol.to_singbox_outbound(&self.version)?;
+        match &mut snippet {
+            ConfigType::Endpoint(value) | ConfigType::Outbound(value) => value["tag"] = json!(tag),
+        }
+        self.snippets.push(snippet);
+        Ok(&self.snippets[self.snippets.len() - 1])
+    }
+
+    // Adds every node, tagged with its remark or `host:port`. Repeated names
+    // get a numeric suffix so the tags stay unique.
+    pub fn add_all(&mut self, protocols: &[Protocol]) -> Result<(), ConversionError> {
+        for protocol in with_unique_names(protocols) {
+            let tag = protocol.display_name();
+            self.add(&protocol, &tag)?;
+        }
+        Ok(())
+    }
+
+    // Always an array, however many snippets there are, so callers can rely on
+    // the shape. The bare object of one node is what `add` returns.
+    pub fn to_value(&self) -> Value {
+        Value::Array(self.snippets.iter().map(|s| s.value().clone()).collect())
+    }
+
+    pub fn to_json(&self) -> Result<String, ConversionError> {
+        serde_json::to_string_pretty(&self.to_value())
+            .map_err(|e| ConversionError::SerializationError(e.to_string()))
+    }
+
+    pub fn save_to_file(&self, filename: &str) -> Result<(), ConversionError> {
+        fs::write(Path::new(filename), self.to_json()?)
+            .map_err(|e| ConversionError::IoError(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snippets_are_always_an_array() {
+        let protocol =
+            Protocol::parse_uri("trojan://secret@example.com:443?type=tcp#Node").unwrap();
+        let mut snippets = OutboundSnippets::new("1.12.0".to_string()).unwrap();
+        let snippet = snippets.add(&protocol, "proxy").unwrap();
+        assert_eq!(snippet.value()["tag"], "proxy");
+        assert_eq!(snippets.to_value().as_array().map(Vec::len), Some(1));
+
+        snippets.add_all(&[protocol.clone(), protocol]).unwrap();
+        let tags: Vec<_> = snippets
+            .to_value()
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|s| s["tag"].clone())
+            .collect();
+        assert_eq!(tags, ["proxy", "Node", "Node 2"]);
+    }
+}
@@ -16,6 +16,7 @@ pub enum ConversionError {
     MissingIP,
     MissingPublicKey,
     MissingRealityParam(String),
+    InvalidRealityParam(String),
     FailedDecode,
     InvalidVmessFormat,
     InvalidJson,
@@ -25,6 +26,12 @@ pub enum ConversionError {
     MissingServersArray,
     MissingTypeField,
     UnsupportedShadowsocks,
+    InvalidFlow(String),
+    IncompatibleTransport(String),
+    TlsRequired(String),
+    InvalidPacketEncoding(String),
+    PayloadTooLarge(usize),
+    UnsupportedPlatform(String),
     Other(String),
 }
 
@@ -45,6 +52,7 @@ impl fmt::Display for ConversionError {
             Self::MissingIP => write!(f, "Missing IP"),
             Self::MissingPublicKey => write!(f, "Missing public key"),
             Self::MissingRealityParam(p) => write!(f, "Missing reality parameter: {}", p),
+            Self::InvalidRealityParam(p) => write!(f, "Invalid reality parameter: {}", p),
             Self::FailedDecode => write!(f, "Failed to decode base64"),
             Self::InvalidVmessFormat => write!(f, "Invalid Vmess format"),
             Self::InvalidJson => write!(f, "Invalid JSON"),
@@ -56,6 +64,16 @@ impl fmt::Display for ConversionError {
             }
             Self::MissingTypeField => write!(f, "Missing type field"),
             Self::UnsupportedShadowsocks => write!(f, "Unsupported Shadowsocks"),
+            Self::InvalidFlow(e) => write!(f, "Invalid flow: {}", e),
+            Self::IncompatibleTransport(e) => write!(f, "Incompatible transport: {}", e),
+            Self::TlsRequired(e) => write!(f, "TLS required: {}", e),
+            Self::InvalidPacketEncoding(e) => write!(f, "Invalid packet encoding: {}", e),
+            Self::PayloadTooLarge(len) => write!(
+                f,
+                "Payload of {} bytes is too large for a QR code (at most 2953 bytes fit)",
+                len
+            ),
+            Self::UnsupportedPlatform(p) => write!(f, "Unsupported platform: {}", p),
             Self::Other(e) => write!(f, "{}", e),
         }
     }
@@ -0,0 +1,45 @@
+// Field readers shared by the importers of structured configs (Xray JSON,
+// Clash YAML), which see every node as a `serde_json::Value`.
+use crate::error::ConversionError;
+use serde_json::Value;
+
+pub(crate) fn parse_port(port: &Value) -> Result<u16, ConversionError> {
+    let invalid = || ConversionError::ParseError(format!("invalid port: {}", port));
+    match port {
+        Value::Null => Err(ConversionError::MissingPort),
+        Value::Number(p) => p
+            .as_u64()
+            .and_then(|p| u16::try_from(p).ok())
+            .ok_or_else(invalid),
+        Value::String(p) => p.parse().map_err(|_| invalid()),
+        _ => Err(invalid()),
+    }
+}
+
+// An optional integer option, rejected rather than truncated when it does not fit.
+pub(crate) fn parse_int<T: TryFrom<u64>>(
+    value: &Value,
+    field: &str,
+) -> Result<Option<T>, ConversionError> {
+    value
+        .as_u64()
+        .map(|v| {
+            T::try_from(v)
+                .map_err(|_| ConversionError::ParseError(format!("{} out of range: {}", field, v)))
+        })
+        .transpose()
+}
+
+// YAML scalars such as passwords may be parsed as numbers.
+pub(crate) fn string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+pub(crate) fn non_empty(value: &Value) -> Option<String> {
+    string(value).filter(|s| !s.is_empty())
+}
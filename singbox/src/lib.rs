@@ -1,3 +1,14 @@
+pub mod clash;
 pub mod config;
 pub mod error;
+mod fields;
+pub mod loon;
+mod profile;
 pub mod protocol;
+pub mod qr;
+pub mod quantumult;
+pub mod sip008;
+pub mod subscription;
+pub mod surge;
+pub mod wireguard;
+pub mod xray;
@@ -0,0 +1,350 @@
+use crate::error::ConversionError;
+use crate::profile::{profile_sections, split_fields, split_options};
+use crate::protocol::tls::{RealityConfig, TlsConfig, UTlsConfig};
+use crate::protocol::transport::TransportConfig;
+use crate::protocol::Protocol;
+use std::collections::HashMap;
+
+// Reads the `[Proxy]` section of a Loon profile. Proxy types we do not support
+// and lines we cannot read are skipped.
+pub fn parse_config(content: &str) -> Result<Vec<Protocol>, ConversionError> {
+    let sections = profile_sections(content);
+    let (_, proxies) = sections
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("Proxy"))
+        .ok_or(ConversionError::MissingField("[Proxy]"))?;
+
+    let mut protocols = Vec::new();
+    for line in proxies {
+        match parse_proxy(line) {
+            Ok(protocol) => protocols.push(protocol),
+            Err(ConversionError::UnsupportedProtocol(proxy_type)) => {
+                log::warn!("Skipping unsupported Loon proxy type: {}", proxy_type);
+            }
+            Err(e) => log::warn!("Skipping Loon proxy line {}: {}", line, e),
+        }
+    }
+    Ok(protocols)
+}
+
+// Parses one `name = Type,host,port,...,key=value` line.
+pub fn parse_proxy(line: &str) -> Result<Protocol, ConversionError> {
+    let (name, definition) = line
+        .split_once('=')
+        .ok_or_else(|| ConversionError::ParseError(format!("invalid proxy line: {}", line)))?;
+    let remark = Some(name.trim().to_string()).filter(|name| !name.is_empty());
+    let (positional, options) = split_options(&split_fields(definition, ','));
+    let option = |key: &str| options.get(key).filter(|v| !v.is_empty()).cloned();
+    let enabled = |key: &str| options.get(key).is_some_and(|v| v == "true");
+
+    let proxy_type = positional
+        .first()
+        .map(|t| t.to_lowercase())
+        .unwrap_or_default();
+    if proxy_type == "wireguard" {
+        return parse_wireguard(&options, remark);
+    }
+
+    let host = positional
+        .get(1)
+        .cloned()
+        .ok_or(ConversionError::MissingHost)?;
+    let port = positional
+        .get(2)
+        .and_then(|port| port.parse().ok())
+        .ok_or(ConversionError::MissingPort)?;
+    // Positional values after the port: `method,"password"` for Shadowsocks
+    // and VMess, just the quoted secret for the rest.
+    let value = |index: usize| positional.get(index).cloned();
+    let tls = |tls: bool| {
+        let reality = option("public-key").map(|public_key| RealityConfig {
+            public_key,
+            short_id: option("short-id").unwrap_or_default(),
+        });
+        TlsConfig {
+            enabled: tls || reality.is_some(),
+            insecure: enabled("skip-cert-verify"),
+            sni: option("tls-name"),
+            utls: reality.as_ref().map(|_| UTlsConfig {
+                enabled: true,
+                fingerprint: "chrome".to_string(),
+            }),
+            reality,
+            ..Default::default()
+        }
+    };
+
+    match proxy_type.as_str() {
+        "shadowsocks" => {
+            let plugin_opts = option("obfs-name").map(|obfs| match option("obfs-host") {
+                Some(host) => format!("obfs={};obfs-host={}", obfs, host),
+                None => format!("obfs={}", obfs),
+            });
+            Ok(Protocol::Shadowsocks {
+                method: value(3).ok_or(ConversionError::MissingField("method"))?,
+                password: value(4).ok_or(ConversionError::MissingPassword)?,
+                host,
+                port,
+                plugin: plugin_opts.is_some().then(|| "obfs-local".to_string()),
+                plugin_opts,
+                remark,
+            })
+        }
+        "shadowsocksr" => Ok(Protocol::ShadowsocksR {
+            method: value(3).ok_or(ConversionError::MissingField("method"))?,
+            password: value(4).ok_or(ConversionError::MissingPassword)?,
+            host,
+            port,
+            ssr_protocol: option("protocol").ok_or(ConversionError::MissingField("protocol"))?,
+            protocol_param: option("protocol-param"),
+            obfs: option("obfs").ok_or(ConversionError::MissingField("obfs"))?,
+            obfs_param: option("obfs-param"),
+            remark,
+        }),
+        "vmess" => Ok(Protocol::Vmess {
+            uuid: value(4).ok_or(ConversionError::MissingUUID)?,
+            host,
+            port,
+            alter_id: option("alterid").unwrap_or_else(|| "0".to_string()),
+            security: value(3).unwrap_or_else(|| "auto".to_string()),
+            packet_encoding: None,
+            transport: parse_transport(&options)?,
+            tls: tls(enabled("over-tls")),
+            remark,
+        }),
+        "vless" => Ok(Protocol::Vless {
+            uuid: value(3).ok_or(ConversionError::MissingUUID)?,
+            host,
+            port,
+            flow: option("flow"),
+            packet_encoding: None,
+            transport: parse_transport(&options)?,
+            tls: tls(enabled("over-tls")),
+            remark,
+        }),
+        "trojan" => Ok(Protocol::Trojan {
+            password: value(3).ok_or(ConversionError::MissingPassword)?,
+            host,
+            port,
+            transport: parse_transport(&options)?,
+            tls: tls(true),
+            remark,
+        }),
+        "hysteria2" => {
+            let obfs_password = option("salamander-password");
+            Ok(Protocol::Hysteria2 {
+                password: value(3).ok_or(ConversionError::MissingPassword)?,
+                host,
+                port,
+                obfs: obfs_password.as_ref().map(|_| "salamander".to_string()),
+                obfs_password,
+                up_mbps: None,
+                down_mbps: option("download-bandwidth").and_then(|down| down.parse().ok()),
+                tls: tls(true),
+                remark,
+            })
+        }
+        proxy_type => Err(ConversionError::UnsupportedProtocol(proxy_type.to_string())),
+    }
+}
+
+fn parse_transport(options: &HashMap<String, String>) -> Result<TransportConfig, ConversionError> {
+    let path = options.get("path").cloned().unwrap_or_default();
+    let host = options.get("host").filter(|host| !host.is_empty()).cloned();
+
+    match options.get("transport").map_or("tcp", String::as_str) {
+        "tcp" => Ok(TransportConfig::Tcp),
+        "ws" => Ok(TransportConfig::Websocket {
+            path,
+            headers: host
+                .map(|host| HashMap::from([("Host".to_string(), host)]))
+                .unwrap_or_default(),
+            max_early_data: 0,
+            early_data_header_name: String::new(),
+        }),
+        "http" => Ok(TransportConfig::Http {
+            host: host.into_iter().collect(),
+            path: if path.is_empty() {
+                "/".to_string()
+            } else {
+                path
+            },
+            method: "GET".to_string(),
+            headers: HashMap::new(),
+            idle_timeout: Some("15s".to_string()),
+            ping_timeout: Some("15s".to_string()),
+        }),
+        transport => Err(ConversionError::InvalidTransportType(transport.to_string())),
+    }
+}
+
+// `interface-ip=...,private-key="...",peers=[{public-key="...",endpoint=...}]`.
+// `Protocol::Wireguard` holds a single peer, so only the first is used.
+fn parse_wireguard(
+    options: &HashMap<String, String>,
+    remark: Option<String>,
+) -> Result<Protocol, ConversionError> {
+    let peers = options
+        .get("peers")
+        .ok_or(ConversionError::MissingField("peers"))?;
+    let peers = peers.trim().trim_start_matches('[').trim_end_matches(']');
+    let peer = split_fields(peers, ',')
+        .first()
+        .map(|peer| {
+            let peer = peer.trim_start_matches('{').trim_end_matches('}');
+            split_options(&split_fields(peer, ',')).1
+        })
+        .ok_or(ConversionError::MissingField("peers"))?;
+
+    let mut ip = Vec::new();
+    if let Some(ipv4) = options.get("interface-ip") {
+        ip.push(if ipv4.contains('/') {
+            ipv4.clone()
+        } else {
+            format!("{}/32", ipv4)
+        });
+    }
+    if let Some(ipv6) = options.get("interface-ipv6") {
+        ip.push(if ipv6.contains('/') {
+            ipv6.clone()
+        } else {
+            format!("{}/128", ipv6)
+        });
+    }
+    if ip.is_empty() {
+        return Err(ConversionError::MissingIP);
+    }
+
+    Ok(Protocol::Wireguard {
+        private_key: options
+            .get("private-key")
+            .cloned()
+            .ok_or(ConversionError::MissingField("private-key"))?,
+        public_key: peer
+            .get("public-key")
+            .cloned()
+            .ok_or(ConversionError::MissingPublicKey)?,
+        endpoint: peer
+            .get("endpoint")
+            .cloned()
+            .ok_or(ConversionError::MissingHost)?,
+        dns: options.get("dns").cloned(),
+        mtu: options.get("mtu").and_then(|mtu| mtu.parse().ok()),
+        ip: ip.join(","),
+        pre_shared_key: peer.get("preshared-key").cloned(),
+        persistent_keepalive: options
+            .get("keepalive")
+            .and_then(|keepalive| keepalive.parse().ok()),
+        amnezia: None,
+        remark,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROFILE: &str = r#"
+[General]
+ipv6 = true
+
+[Proxy]
+SS = Shadowsocks,example.com,8388,aes-256-gcm,"pa,ss=word",obfs-name=tls,obfs-host=cdn.example.com,udp=true
+VMess = VMess,example.com,443,auto,"uuid",transport=ws,path=/ws,host=cdn.example.com,alterId=0,over-tls=true,tls-name=sni.example.com
+Reality = VLESS,example.com,443,"uuid",transport=tcp,flow=xtls-rprx-vision,over-tls=true,tls-name=sni.example.com,public-key="pbk",short-id=ab12
+Hy2 = Hysteria2,example.com,443,"secret",salamander-password=obfs,download-bandwidth=100
+Tuic = TUIC,example.com,443,"secret"
+Broken = VMess,example.com
+WG = WireGuard,interface-ip=10.0.0.2,interface-ipv6=fd00::2,private-key="priv",mtu=1280,dns=1.1.1.1,keepalive=25,peers=[{public-key="pub",allowed-ips="0.0.0.0/0,::/0",endpoint=example.com:51820,preshared-key="psk"}]
+"#;
+
+    #[test]
+    fn proxy_section_is_read() {
+        let protocols = parse_config(PROFILE).unwrap();
+        let names: Vec<_> = protocols.iter().map(|p| p.remark().unwrap()).collect();
+        assert_eq!(names, vec!["SS", "VMess", "Reality", "Hy2", "WG"]);
+
+        let Protocol::Shadowsocks {
+            method, password, ..
+        } = &protocols[0]
+        else {
+            panic!("expected Shadowsocks");
+        };
+        assert_eq!(method, "aes-256-gcm");
+        assert_eq!(password, "pa,ss=word");
+
+        let Protocol::Vmess {
+            uuid,
+            transport,
+            tls,
+            ..
+        } = &protocols[1]
+        else {
+            panic!("expected VMess");
+        };
+        assert_eq!(uuid, "uuid");
+        assert_eq!(
+            transport,
+            &TransportConfig::Websocket {
+                path: "/ws".to_string(),
+                headers: HashMap::from([("Host".to_string(), "cdn.example.com".to_string())]),
+                max_early_data: 0,
+                early_data_header_name: String::new(),
+            }
+        );
+        assert_eq!(tls.sni.as_deref(), Some("sni.example.com"));
+
+        let Protocol::Vless { flow, tls, .. } = &protocols[2] else {
+            panic!("expected VLESS");
+        };
+        assert_eq!(flow.as_deref(), Some("xtls-rprx-vision"));
+        assert_eq!(
+            tls.reality,
+            Some(RealityConfig {
+                public_key: "pbk".to_string(),
+                short_id: "ab12".to_string(),
+            })
+        );
+
+        let Protocol::Hysteria2 {
+            obfs, down_mbps, ..
+        } = &protocols[3]
+        else {
+            panic!("expected Hysteria2");
+        };
+        assert_eq!(obfs.as_deref(), Some("salamander"));
+        assert_eq!(*down_mbps, Some(100));
+    }
+
+    #[test]
+    fn wireguard_peers_are_read() {
+        let protocols = parse_config(PROFILE).unwrap();
+        assert_eq!(
+            protocols[4],
+            Protocol::Wireguard {
+                private_key: "priv".to_string(),
+                public_key: "pub".to_string(),
+                endpoint: "example.com:51820".to_string(),
+                dns: Some("1.1.1.1".to_string()),
+                mtu: Some(1280),
+                ip: "10.0.0.2/32,fd00::2/128".to_string(),
+                pre_shared_key: Some("psk".to_string()),
+                persistent_keepalive: Some(25),
+                amnezia: None,
+                remark: Some("WG".to_string()),
+            }
+        );
+        assert!(matches!(
+            parse_proxy(r#"WG = WireGuard,interface-ip=10.0.0.2,private-key="priv""#),
+            Err(ConversionError::MissingField("peers"))
+        ));
+    }
+
+    #[test]
+    fn exported_lines_read_back() {
+        for protocol in parse_config(PROFILE).unwrap() {
+            let line = protocol.to_loon_proxy().unwrap();
+            assert_eq!(parse_proxy(&line).unwrap(), protocol, "{}", line);
+        }
+    }
+}
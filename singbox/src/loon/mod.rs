@@ -0,0 +1,98 @@
+pub mod import;
+pub mod proxy;
+use crate::profile::with_profile_names;
+use crate::protocol::Protocol;
+
+// A minimal Loon profile: one `select` group over every node Loon can carry,
+// Iranian and private destinations direct, everything else proxied. Nodes
+// Loon does not support are skipped.
+pub fn profile(protocols: &[Protocol]) -> String {
+    let mut proxies = Vec::new();
+    let mut names = Vec::new();
+    for protocol in with_profile_names(protocols, &[',', '=']) {
+        match protocol.to_loon_proxy() {
+            Ok(line) => {
+                names.push(protocol.display_name());
+                proxies.push(line);
+            }
+            Err(e) => log::warn!(
+                "Skipping {} in Loon profile: {}",
+                protocol.display_name(),
+                e
+            ),
+        }
+    }
+
+    // An empty group is rejected by the client; offer DIRECT instead.
+    if names.is_empty() {
+        names.push("DIRECT".to_string());
+    }
+
+    let mut profile = vec![
+        "[General]".to_string(),
+        "dns-server = system,1.1.1.1".to_string(),
+        "skip-proxy = 192.168.0.0/16,10.0.0.0/8,172.16.0.0/12,localhost,*.local".to_string(),
+        "ipv6 = true".to_string(),
+        String::new(),
+        "[Proxy]".to_string(),
+    ];
+    profile.extend(proxies);
+    profile.extend([
+        String::new(),
+        "[Proxy Group]".to_string(),
+        format!("PROXY = select,{}", names.join(",")),
+        String::new(),
+        "[Rule]".to_string(),
+        "IP-CIDR,192.168.0.0/16,DIRECT".to_string(),
+        "IP-CIDR,10.0.0.0/8,DIRECT".to_string(),
+        "IP-CIDR,172.16.0.0/12,DIRECT".to_string(),
+        "DOMAIN-SUFFIX,ir,DIRECT".to_string(),
+        "GEOIP,IR,DIRECT".to_string(),
+        "FINAL,PROXY".to_string(),
+    ]);
+    profile.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::tls::TlsConfig;
+    use crate::protocol::transport::TransportConfig;
+
+    fn trojan(name: &str) -> Protocol {
+        Protocol::Trojan {
+            password: "secret".to_string(),
+            host: "example.com".to_string(),
+            port: 443,
+            transport: TransportConfig::Tcp,
+            tls: TlsConfig {
+                enabled: true,
+                ..Default::default()
+            },
+            remark: Some(name.to_string()),
+        }
+    }
+
+    #[test]
+    fn names_are_cleaned_before_they_are_made_unique() {
+        let profile = profile(&[trojan("a,b"), trojan("a=b")]);
+        let lines: Vec<_> = profile.lines().collect();
+        assert!(lines.contains(&"PROXY = select,a b,a b 2"));
+        assert_eq!(
+            lines.iter().filter(|l| l.contains("a b = trojan")).count(),
+            1
+        );
+        assert_eq!(
+            lines
+                .iter()
+                .filter(|l| l.contains("a b 2 = trojan"))
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn no_nodes_fall_back_to_direct() {
+        assert!(profile(&[]).lines().any(|l| l == "PROXY = select,DIRECT"));
+    }
+}
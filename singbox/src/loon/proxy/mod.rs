@@ -0,0 +1,367 @@
+use crate::error::ConversionError;
+use crate::protocol::tls::TlsConfig;
+use crate::protocol::transport::TransportConfig;
+use crate::protocol::{parse_alter_id, plugin_options, split_addresses, Protocol, SIMPLE_OBFS};
+
+impl Protocol {
+    // A Loon `[Proxy]` line: `name = Type,host,port,...` with quoted secrets.
+    pub fn to_loon_proxy(&self) -> Result<String, ConversionError> {
+        let name = self.display_name().replace([',', '='], " ");
+        let mut fields = Vec::new();
+
+        match self {
+            Self::Shadowsocks {
+                method,
+                password,
+                host,
+                port,
+                plugin,
+                plugin_opts,
+                ..
+            } => {
+                fields.extend([
+                    "Shadowsocks".to_string(),
+                    host.clone(),
+                    port.to_string(),
+                    method.clone(),
+                    format!("\"{}\"", password),
+                ]);
+                if let Some(plugin) = plugin {
+                    if !SIMPLE_OBFS.contains(&plugin.as_str()) {
+                        return Err(unsupported(&format!("the {} plugin", plugin)));
+                    }
+                    let opts = plugin_options(plugin_opts.as_deref());
+                    fields.push(format!(
+                        "obfs-name={}",
+                        opts.get("obfs").map_or("http", String::as_str)
+                    ));
+                    if let Some(host) = opts.get("obfs-host") {
+                        fields.push(format!("obfs-host={}", host));
+                    }
+                }
+                fields.push("udp=true".to_string());
+            }
+            Self::ShadowsocksR {
+                method,
+                password,
+                host,
+                port,
+                ssr_protocol,
+                protocol_param,
+                obfs,
+                obfs_param,
+                ..
+            } => {
+                fields.extend([
+                    "ShadowsocksR".to_string(),
+                    host.clone(),
+                    port.to_string(),
+                    method.clone(),
+                    format!("\"{}\"", password),
+                    format!("protocol={}", ssr_protocol),
+                ]);
+                if let Some(protocol_param) = protocol_param {
+                    fields.push(format!("protocol-param={}", protocol_param));
+                }
+                fields.push(format!("obfs={}", obfs));
+                if let Some(obfs_param) = obfs_param {
+                    fields.push(format!("obfs-param={}", obfs_param));
+                }
+                fields.push("udp=true".to_string());
+            }
+            Self::Vmess {
+                uuid,
+                host,
+                port,
+                alter_id,
+                security,
+                transport,
+                tls,
+                ..
+            } => {
+                fields.extend([
+                    "VMess".to_string(),
+                    host.clone(),
+                    port.to_string(),
+                    security.clone(),
+                    format!("\"{}\"", uuid),
+                ]);
+                fields.extend(transport_fields(transport)?);
+                fields.push(format!("alterId={}", parse_alter_id(alter_id)?));
+                if tls.enabled {
+                    fields.push("over-tls=true".to_string());
+                    fields.extend(tls_fields(tls));
+                }
+            }
+            Self::Vless {
+                uuid,
+                host,
+                port,
+                flow,
+                transport,
+                tls,
+                ..
+            } => {
+                fields.extend([
+                    "VLESS".to_string(),
+                    host.clone(),
+                    port.to_string(),
+                    format!("\"{}\"", uuid),
+                ]);
+                fields.extend(transport_fields(transport)?);
+                if let Some(flow) = flow {
+                    fields.push(format!("flow={}", flow));
+                }
+                if tls.enabled {
+                    fields.push("over-tls=true".to_string());
+                    fields.extend(tls_fields(tls));
+                }
+            }
+            Self::Trojan {
+                password,
+                host,
+                port,
+                transport,
+                tls,
+                ..
+            } => {
+                fields.extend([
+                    "trojan".to_string(),
+                    host.clone(),
+                    port.to_string(),
+                    format!("\"{}\"", password),
+                ]);
+                fields.extend(transport_fields(transport)?);
+                fields.extend(tls_fields(tls));
+            }
+            Self::Hysteria2 {
+                password,
+                host,
+                port,
+                obfs,
+                obfs_password,
+                down_mbps,
+                tls,
+                ..
+            } => {
+                fields.extend([
+                    "Hysteria2".to_string(),
+                    host.clone(),
+                    port.to_string(),
+                    format!("\"{}\"", password),
+                ]);
+                match (obfs.as_deref(), obfs_password) {
+                    (None, _) => {}
+                    (Some("salamander"), Some(obfs_password)) => {
+                        fields.push(format!("salamander-password={}", obfs_password))
+                    }
+                    (Some(obfs), _) => {
+                        return Err(unsupported(&format!("Hysteria2 {} obfuscation", obfs)))
+                    }
+                }
+                fields.extend(tls_fields(tls));
+                if let Some(down) = down_mbps {
+                    fields.push(format!("download-bandwidth={}", down));
+                }
+                fields.push("udp=true".to_string());
+            }
+            Self::Wireguard {
+                private_key,
+                public_key,
+                endpoint,
+                dns,
+                mtu,
+                ip,
+                pre_shared_key,
+                persistent_keepalive,
+                amnezia,
+                ..
+            } => {
+                if amnezia.is_some() {
+                    return Err(unsupported("AmneziaWG obfuscation"));
+                }
+                fields.push("WireGuard".to_string());
+                for address in split_addresses(ip) {
+                    fields.push(if address.contains(':') {
+                        format!("interface-ipv6={}", address.trim_end_matches("/128"))
+                    } else {
+                        format!("interface-ip={}", address.trim_end_matches("/32"))
+                    });
+                }
+                fields.push(format!("private-key=\"{}\"", private_key));
+                if let Some(mtu) = mtu {
+                    fields.push(format!("mtu={}", mtu));
+                }
+                // Loon takes a single DNS server.
+                if let Some(dns) = dns.as_deref().and_then(|dns| dns.split(',').next()) {
+                    fields.push(format!("dns={}", dns.trim()));
+                }
+                if let Some(keepalive) = persistent_keepalive {
+                    fields.push(format!("keepalive={}", keepalive));
+                }
+                let mut peer = vec![
+                    format!("public-key=\"{}\"", public_key),
+                    "allowed-ips=\"0.0.0.0/0,::/0\"".to_string(),
+                    format!("endpoint={}", endpoint),
+                ];
+                if let Some(pre_shared_key) = pre_shared_key {
+                    peer.push(format!("preshared-key=\"{}\"", pre_shared_key));
+                }
+                fields.push(format!("peers=[{{{}}}]", peer.join(",")));
+            }
+            Self::Tuic { .. } => return Err(unsupported("TUIC")),
+        }
+
+        Ok(format!("{} = {}", name, fields.join(",")))
+    }
+}
+
+fn transport_fields(transport: &TransportConfig) -> Result<Vec<String>, ConversionError> {
+    let (kind, path, host) = match transport {
+        TransportConfig::Tcp => return Ok(vec!["transport=tcp".to_string()]),
+        TransportConfig::Websocket { path, headers, .. } => ("ws", path, headers.get("Host")),
+        TransportConfig::Http { path, host, .. } => ("http", path, host.first()),
+        _ => return Err(unsupported("transports other than tcp, ws and http")),
+    };
+
+    let mut fields = vec![format!("transport={}", kind)];
+    if !path.is_empty() {
+        fields.push(format!("path={}", path));
+    }
+    if let Some(host) = host {
+        fields.push(format!("host={}", host));
+    }
+    Ok(fields)
+}
+
+// VMess and VLESS also need `over-tls=true`; Trojan and Hysteria2 are always TLS.
+fn tls_fields(tls: &TlsConfig) -> Vec<String> {
+    let mut fields = Vec::new();
+    if let Some(sni) = &tls.sni {
+        fields.push(format!("tls-name={}", sni));
+    }
+    if tls.insecure {
+        fields.push("skip-cert-verify=true".to_string());
+    }
+    if let Some(reality) = &tls.reality {
+        fields.push(format!("public-key=\"{}\"", reality.public_key));
+        if !reality.short_id.is_empty() {
+            fields.push(format!("short-id={}", reality.short_id));
+        }
+    }
+    fields
+}
+
+fn unsupported(feature: &str) -> ConversionError {
+    ConversionError::UnsupportedFeature(format!("Loon does not support {}", feature))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::tls::RealityConfig;
+    use std::collections::HashMap;
+
+    fn vmess(alter_id: &str) -> Protocol {
+        Protocol::Vmess {
+            uuid: "uuid".to_string(),
+            host: "example.com".to_string(),
+            port: 443,
+            alter_id: alter_id.to_string(),
+            security: "auto".to_string(),
+            packet_encoding: None,
+            transport: TransportConfig::Websocket {
+                path: "/ws".to_string(),
+                headers: HashMap::from([("Host".to_string(), "cdn.example.com".to_string())]),
+                max_early_data: 0,
+                early_data_header_name: String::new(),
+            },
+            tls: TlsConfig {
+                enabled: true,
+                sni: Some("sni.example.com".to_string()),
+                ..Default::default()
+            },
+            remark: Some("V".to_string()),
+        }
+    }
+
+    #[test]
+    fn shadowsocks_line_quotes_the_password() {
+        let ss = Protocol::Shadowsocks {
+            method: "aes-256-gcm".to_string(),
+            password: "se,cret".to_string(),
+            host: "example.com".to_string(),
+            port: 8388,
+            plugin: Some("obfs-local".to_string()),
+            plugin_opts: Some("obfs=tls;obfs-host=cdn.example.com".to_string()),
+            remark: Some("S=1".to_string()),
+        };
+        assert_eq!(
+            ss.to_loon_proxy().unwrap(),
+            "S 1 = Shadowsocks,example.com,8388,aes-256-gcm,\"se,cret\",\
+             obfs-name=tls,obfs-host=cdn.example.com,udp=true"
+        );
+    }
+
+    #[test]
+    fn vmess_line_carries_ws_and_tls() {
+        assert_eq!(
+            vmess("0").to_loon_proxy().unwrap(),
+            "V = VMess,example.com,443,auto,\"uuid\",transport=ws,path=/ws,\
+             host=cdn.example.com,alterId=0,over-tls=true,tls-name=sni.example.com"
+        );
+        assert!(matches!(
+            vmess("x").to_loon_proxy(),
+            Err(ConversionError::ParseError(_))
+        ));
+    }
+
+    #[test]
+    fn vless_line_carries_reality() {
+        let vless = Protocol::Vless {
+            uuid: "uuid".to_string(),
+            host: "example.com".to_string(),
+            port: 443,
+            flow: Some("xtls-rprx-vision".to_string()),
+            packet_encoding: None,
+            transport: TransportConfig::Tcp,
+            tls: TlsConfig {
+                enabled: true,
+                sni: Some("sni.example.com".to_string()),
+                reality: Some(RealityConfig {
+                    public_key: "pbk".to_string(),
+                    short_id: "ab12".to_string(),
+                }),
+                ..Default::default()
+            },
+            remark: Some("R".to_string()),
+        };
+        assert_eq!(
+            vless.to_loon_proxy().unwrap(),
+            "R = VLESS,example.com,443,\"uuid\",transport=tcp,flow=xtls-rprx-vision,\
+             over-tls=true,tls-name=sni.example.com,public-key=\"pbk\",short-id=ab12"
+        );
+    }
+
+    #[test]
+    fn wireguard_line_lists_both_families_and_one_peer() {
+        let wireguard = Protocol::Wireguard {
+            private_key: "priv".to_string(),
+            public_key: "pub".to_string(),
+            endpoint: "example.com:51820".to_string(),
+            dns: Some("1.1.1.1,8.8.8.8".to_string()),
+            mtu: Some(1280),
+            ip: "10.0.0.2/32,fd00::2/128".to_string(),
+            pre_shared_key: None,
+            persistent_keepalive: Some(25),
+            amnezia: None,
+            remark: Some("W".to_string()),
+        };
+        assert_eq!(
+            wireguard.to_loon_proxy().unwrap(),
+            "W = WireGuard,interface-ip=10.0.0.2,interface-ipv6=fd00::2,private-key=\"priv\",\
+             mtu=1280,dns=1.1.1.1,keepalive=25,peers=[{public-key=\"pub\",\
+             allowed-ips=\"0.0.0.0/0,::/0\",endpoint=example.com:51820}]"
+        );
+    }
+}
@@ -1,8 +1,19 @@
 use singbox::config;
+use singbox::config::snippet::OutboundSnippets;
+use singbox::error::ConversionError;
 use singbox::protocol::Protocol;
 
 #[tokio::main]
 async fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|mode| mode == "outbound") {
+        if let Err(e) = print_outbounds(&args[1..]) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let version = "1.12.0".to_string();
 
     let uris = [
@@ -11,43 +22,66 @@ async fn main() {
     ];
 
     for uri in uris {
-        match Protocol::parse_uri(uri) {
-            Ok(protocol) => {
-                let mut config = match config::SingBoxConfig::new(version.clone()) {
-                    // Replace with actual version
-                    Ok(config) => config,
-                    Err(e) => {
-                        eprintln!("Failed to create config: {}", e);
-                        continue;
-                    }
-                };
-                config.set_log_level("info");
-                config.add_dns_server();
-
-                config.add_dns_rule();
-
-                config.add_mixed_inbound();
-                config.add_tun_inbound();
-
-                if let Err(e) = config.add_outbound(protocol) {
-                    eprintln!("Failed to add outbound: {}", e);
-                    continue;
-                }
-                config.set_route();
-                config.add_default_experimental();
-
-                let filename = format!(
-                    "GeneratedConfig/config_{}.json",
-                    chrono::Local::now().format("%Y%m%d%H%M%S")
-                );
-
-                if let Err(e) = config.save_to_file(&filename) {
-                    eprintln!("Failed to save config to '{}': {}", filename, e);
-                }
-            }
-            Err(e) => {
-                eprintln!("Failed to parse URI '{}': {}", uri, e);
-            }
+        if let Err(e) = save_config(&version, uri) {
+            eprintln!("Failed to convert URI '{}': {}", uri, e);
         }
     }
 }
+
+fn save_config(version: &str, uri: &str) -> Result<(), ConversionError> {
+    let protocol = Protocol::parse_uri(uri)?;
+    let mut config = config::SingBoxConfig::new(version.to_string())?;
+    config.set_log_level("info");
+    config.add_dns_server();
+
+    config.add_dns_rule();
+
+    config.add_mixed_inbound();
+    config.add_tun_inbound();
+
+    config.add_outbound(protocol)?;
+    config.set_route();
+    config.add_default_experimental();
+
+    let filename = format!(
+        "GeneratedConfig/config_{}.json",
+        chrono::Local::now().format("%Y%m%d%H%M%S")
+    );
+    config.save_to_file(&filename)
+}
+
+// `outbound <version> [--tag <tag>] <URI>...` prints only the outbound or
+// endpoint objects, as a JSON array. Without `--tag` each node is tagged with
+// its remark; `--tag` names a single node, so it takes exactly one URI.
+fn print_outbounds(args: &[String]) -> Result<(), String> {
+    let usage = "Usage: outbound <version> [--tag <tag>] <URI>...";
+    let (version, mut args) = args.split_first().ok_or(usage)?;
+    let mut tag = None;
+    if let [flag, value, rest @ ..] = args {
+        if flag == "--tag" {
+            tag = Some(value.clone());
+            args = rest;
+        }
+    }
+    if args.is_empty() {
+        return Err(usage.to_string());
+    }
+    if tag.is_some() && args.len() > 1 {
+        return Err(format!("--tag takes a single URI\n{}", usage));
+    }
+
+    let mut protocols = Vec::new();
+    for uri in args {
+        let mut protocol = Protocol::parse_uri(uri)
+            .map_err(|e| format!("Failed to parse URI '{}': {}", uri, e))?;
+        if tag.is_some() {
+            protocol.set_remark(tag.clone());
+        }
+        protocols.push(protocol);
+    }
+
+    let mut snippets = OutboundSnippets::new(version.clone()).map_err(|e| e.to_string())?;
+    snippets.add_all(&protocols).map_err(|e| e.to_string())?;
+    println!("{}", snippets.to_json().map_err(|e| e.to_string())?);
+    Ok(())
+}
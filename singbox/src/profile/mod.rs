@@ -0,0 +1,144 @@
+// Line-oriented parsing shared by the Surge, Loon and Quantumult X profile
+// importers, and the node naming their exporters share.
+use crate::protocol::{with_unique_names, Protocol};
+use std::collections::HashMap;
+
+// Profile lines use some characters as separators, so names have them replaced
+// by spaces. This happens before names are made unique, so two cleaned names
+// cannot collide again.
+pub(crate) fn with_profile_names(protocols: &[Protocol], reserved: &[char]) -> Vec<Protocol> {
+    let cleaned: Vec<Protocol> = protocols
+        .iter()
+        .map(|protocol| {
+            let mut protocol = protocol.clone();
+            protocol.set_remark(Some(protocol.display_name().replace(reserved, " ")));
+            protocol
+        })
+        .collect();
+    with_unique_names(&cleaned)
+}
+
+// Groups the lines of a Surge / Loon / Quantumult X profile by `[Section]`,
+// dropping blank lines and comments. Lines before the first header are kept
+// under an unnamed section.
+pub(crate) fn profile_sections(content: &str) -> Vec<(&str, Vec<&str>)> {
+    let mut sections: Vec<(&str, Vec<&str>)> = vec![("", Vec::new())];
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with(['#', ';']) || line.starts_with("//") {
+            continue;
+        }
+        if line.starts_with('[') && line.ends_with(']') {
+            sections.push((line[1..line.len() - 1].trim(), Vec::new()));
+        } else if let Some((_, lines)) = sections.last_mut() {
+            lines.push(line);
+        }
+    }
+    sections
+}
+
+// Splits a proxy line on `separator`, leaving quoted values and bracketed
+// groups such as Loon's `peers=[{...}]` intact.
+pub(crate) fn split_fields(line: &str, separator: char) -> Vec<&str> {
+    let mut fields = Vec::new();
+    let (mut start, mut depth, mut quoted) = (0, 0, false);
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '[' | '{' | '(' if !quoted => depth += 1,
+            ']' | '}' | ')' if !quoted => depth -= 1,
+            c if c == separator && !quoted && depth == 0 => {
+                fields.push(line[start..i].trim());
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    fields.push(line[start..].trim());
+    fields.retain(|field| !field.is_empty());
+    fields
+}
+
+// Separates positional values from `key=value` options (keys lowercased).
+// Quoted values are always positional, since passwords may contain `=`.
+pub(crate) fn split_options(fields: &[&str]) -> (Vec<String>, HashMap<String, String>) {
+    let mut positional = Vec::new();
+    let mut options = HashMap::new();
+    for field in fields {
+        match field.split_once('=') {
+            Some((key, value)) if !field.starts_with('"') => {
+                options.insert(key.trim().to_lowercase(), unquote(value.trim()).to_string());
+            }
+            _ => positional.push(unquote(field).to_string()),
+        }
+    }
+    (positional, options)
+}
+
+pub(crate) fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sections_skip_comments_and_keep_leading_lines() {
+        let sections =
+            profile_sections("a = 1\n# comment\n\n[Proxy]\n; note\n// note\nb = 2\n[ Rule ]\n");
+        assert_eq!(
+            sections,
+            vec![
+                ("", vec!["a = 1"]),
+                ("Proxy", vec!["b = 2"]),
+                ("Rule", vec![])
+            ]
+        );
+    }
+
+    #[test]
+    fn fields_keep_quotes_and_brackets_intact() {
+        assert_eq!(
+            split_fields(
+                r#"Shadowsocks, host, 443, aes-256-gcm,"pa,ss", peers=[{a=1,b="2,3"}], x=(1, 2),,"#,
+                ','
+            ),
+            vec![
+                "Shadowsocks",
+                "host",
+                "443",
+                "aes-256-gcm",
+                r#""pa,ss""#,
+                r#"peers=[{a=1,b="2,3"}]"#,
+                "x=(1, 2)",
+            ]
+        );
+        assert_eq!(
+            split_fields(r#"Host:"a|b"|X:"c""#, '|'),
+            vec![r#"Host:"a|b""#, r#"X:"c""#]
+        );
+    }
+
+    #[test]
+    fn quoted_values_stay_positional() {
+        let (positional, options) = split_options(&[
+            "trojan",
+            r#""pa=ss""#,
+            r#"Password="x,y""#,
+            "SNI = example.com",
+        ]);
+        assert_eq!(positional, vec!["trojan", "pa=ss"]);
+        assert_eq!(
+            options,
+            HashMap::from([
+                ("password".to_string(), "x,y".to_string()),
+                ("sni".to_string(), "example.com".to_string()),
+            ])
+        );
+        assert_eq!(unquote(r#""a""#), "a");
+        assert_eq!(unquote(r#""a"#), r#""a"#);
+    }
+}
@@ -0,0 +1,298 @@
+use std::collections::HashMap;
+
+// Canonical query keys and the spellings other clients use for them. Parsers
+// only ever look up the canonical key, so normalize the query map first.
+//
+// Dialects covered:
+// - v2rayN / v2rayNG: sni, fp, allowInsecure, serviceName, headerType
+// - NekoBox / sing-box URIs: insecure, allow_insecure, service_name, peer
+// - Shadowrocket: peer, allowInsecure, obfs-host, obfsParam
+// - Hiddify: fingerprint, serverName, insecure, publicKey, shortId, spiderX
+// - Clash / mihomo: servername, skip-cert-verify, client-fingerprint,
+//   grpc-service-name, public-key, short-id
+const STREAM_ALIASES: &[(&str, &[&str])] = &[
+    ("type", &["network", "net"]),
+    ("sni", &["peer", "serverName", "servername", "server_name"]),
+    (
+        "insecure",
+        &[
+            "allowInsecure",
+            "allow_insecure",
+            "allowinsecure",
+            "skip-cert-verify",
+            "skip_cert_verify",
+        ],
+    ),
+    ("fp", &["fingerprint", "client-fingerprint", "utls"]),
+    (
+        "serviceName",
+        &[
+            "service_name",
+            "servicename",
+            "service-name",
+            "grpc-service-name",
+        ],
+    ),
+    ("host", &["obfs-host", "obfsParam", "ws-host"]),
+    ("path", &["ws-path", "obfs-uri"]),
+    ("headerType", &["header_type", "header-type"]),
+    ("pbk", &["publicKey", "public_key", "public-key"]),
+    ("sid", &["shortId", "short_id", "short-id"]),
+    ("spx", &["spiderX", "spider_x", "spider-x"]),
+    ("packetEncoding", &["packet_encoding", "packet-encoding"]),
+    ("obfs-password", &["obfs_password", "obfsPassword"]),
+    (
+        "congestion_control",
+        &[
+            "congestion-control",
+            "congestion_controller",
+            "congestion-controller",
+        ],
+    ),
+    ("udp_relay_mode", &["udp-relay-mode", "udpRelayMode"]),
+];
+
+const SHADOWSOCKS_ALIASES: &[(&str, &[&str])] = &[("plugin-opts", &["plugin_opts", "pluginOpts"])];
+
+const WIREGUARD_ALIASES: &[(&str, &[&str])] = &[
+    (
+        "publickey",
+        &["publicKey", "public_key", "public-key", "peer_public_key"],
+    ),
+    ("ip", &["address", "local_address"]),
+    (
+        "presharedkey",
+        &["preSharedKey", "pre_shared_key", "pre-shared-key", "psk"],
+    ),
+    (
+        "keepalive",
+        &[
+            "persistentKeepalive",
+            "persistent_keepalive",
+            "persistent-keepalive",
+        ],
+    ),
+];
+
+// Renames every alias in `query` to its canonical key for `scheme`. An
+// explicitly canonical key wins; otherwise the first alias in table order does.
+pub fn normalize_query(scheme: &str, query: &mut HashMap<String, String>) {
+    let table = match scheme {
+        "vmess" | "vless" | "trojan" | "hysteria2" | "tuic" => STREAM_ALIASES,
+        "ss" => SHADOWSOCKS_ALIASES,
+        "wireguard" => WIREGUARD_ALIASES,
+        _ => return,
+    };
+
+    for (canonical, aliases) in table {
+        for alias in aliases.iter() {
+            if let Some(value) = query.remove(*alias) {
+                query.entry(canonical.to_string()).or_insert(value);
+            }
+        }
+    }
+}
+
+// Boolean query values come as 1/0, true/false or yes/no depending on the client.
+pub fn is_truthy(value: &str) -> bool {
+    matches!(value.to_lowercase().as_str(), "1" | "true" | "yes" | "on")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::transport::TransportConfig;
+    use crate::protocol::Protocol;
+    use base64::engine::general_purpose;
+    use base64::Engine;
+
+    fn normalized(scheme: &str, pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        let mut query = pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        normalize_query(scheme, &mut query);
+        query
+    }
+
+    fn canonical(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn v2rayn_vless_keys() {
+        let query = normalized(
+            "vless",
+            &[
+                ("type", "grpc"),
+                ("serviceName", "svc"),
+                ("sni", "example.com"),
+                ("fp", "firefox"),
+                ("allowInsecure", "1"),
+                ("headerType", "none"),
+            ],
+        );
+        assert_eq!(
+            query,
+            canonical(&[
+                ("type", "grpc"),
+                ("serviceName", "svc"),
+                ("sni", "example.com"),
+                ("fp", "firefox"),
+                ("insecure", "1"),
+                ("headerType", "none"),
+            ])
+        );
+    }
+
+    #[test]
+    fn v2rayn_vmess_grpc_service_name_in_path() {
+        let json = r#"{"v":"2","ps":"node","add":"example.com","port":"443","id":"uuid","aid":"0","scy":"auto","net":"grpc","type":"gun","path":"svc","tls":"tls","sni":"example.com","allowInsecure":"true"}"#;
+        let uri = format!("vmess://{}", general_purpose::STANDARD.encode(json));
+        match Protocol::parse_uri(&uri).unwrap() {
+            Protocol::Vmess { transport, tls, .. } => {
+                assert!(matches!(
+                    transport,
+                    TransportConfig::Grpc { ref service_name, .. } if service_name == "svc"
+                ));
+                assert!(tls.insecure);
+                assert_eq!(tls.sni.as_deref(), Some("example.com"));
+            }
+            other => panic!("expected vmess, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn nekobox_keys() {
+        let query = normalized(
+            "trojan",
+            &[
+                ("network", "grpc"),
+                ("service_name", "svc"),
+                ("allow_insecure", "1"),
+                ("server_name", "example.com"),
+                ("packet_encoding", "xudp"),
+            ],
+        );
+        assert_eq!(
+            query,
+            canonical(&[
+                ("type", "grpc"),
+                ("serviceName", "svc"),
+                ("insecure", "1"),
+                ("sni", "example.com"),
+                ("packetEncoding", "xudp"),
+            ])
+        );
+    }
+
+    #[test]
+    fn shadowrocket_keys() {
+        let query = normalized(
+            "vless",
+            &[
+                ("peer", "example.com"),
+                ("allowInsecure", "1"),
+                ("obfsParam", "cdn.example.com"),
+                ("obfs-uri", "/ws"),
+            ],
+        );
+        assert_eq!(
+            query,
+            canonical(&[
+                ("sni", "example.com"),
+                ("insecure", "1"),
+                ("host", "cdn.example.com"),
+                ("path", "/ws"),
+            ])
+        );
+    }
+
+    #[test]
+    fn hiddify_reality_keys() {
+        let uri = "vless://uuid@example.com:443?type=tcp&security=reality&serverName=www.example.com&fingerprint=safari&publicKey=pbk&shortId=ab&spiderX=%2F#node";
+        match Protocol::parse_uri(uri).unwrap() {
+            Protocol::Vless { tls, .. } => {
+                assert_eq!(tls.sni.as_deref(), Some("www.example.com"));
+                assert_eq!(tls.utls.unwrap().fingerprint, "safari");
+                let reality = tls.reality.unwrap();
+                assert_eq!(reality.public_key, "pbk");
+                assert_eq!(reality.short_id, "ab");
+            }
+            other => panic!("expected vless, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn clash_keys() {
+        let query = normalized(
+            "vless",
+            &[
+                ("servername", "example.com"),
+                ("skip-cert-verify", "true"),
+                ("client-fingerprint", "chrome"),
+                ("grpc-service-name", "svc"),
+                ("public-key", "pbk"),
+                ("short-id", "ab"),
+            ],
+        );
+        assert_eq!(
+            query,
+            canonical(&[
+                ("sni", "example.com"),
+                ("insecure", "true"),
+                ("fp", "chrome"),
+                ("serviceName", "svc"),
+                ("pbk", "pbk"),
+                ("sid", "ab"),
+            ])
+        );
+    }
+
+    #[test]
+    fn canonical_key_wins_over_alias() {
+        let query = normalized(
+            "vless",
+            &[("sni", "a.example.com"), ("peer", "b.example.com")],
+        );
+        assert_eq!(query, canonical(&[("sni", "a.example.com")]));
+    }
+
+    #[test]
+    fn shadowsocks_and_wireguard_tables() {
+        assert_eq!(
+            normalized("ss", &[("plugin_opts", "obfs=http")]),
+            canonical(&[("plugin-opts", "obfs=http")])
+        );
+        assert_eq!(
+            normalized(
+                "wireguard",
+                &[
+                    ("publicKey", "pk"),
+                    ("address", "10.0.0.2/32"),
+                    ("psk", "psk"),
+                    ("persistent_keepalive", "25"),
+                ],
+            ),
+            canonical(&[
+                ("publickey", "pk"),
+                ("ip", "10.0.0.2/32"),
+                ("presharedkey", "psk"),
+                ("keepalive", "25"),
+            ])
+        );
+    }
+
+    #[test]
+    fn truthy_values() {
+        for value in ["1", "true", "TRUE", "yes", "on"] {
+            assert!(is_truthy(value), "{}", value);
+        }
+        for value in ["0", "false", "no", ""] {
+            assert!(!is_truthy(value), "{}", value);
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use crate::error::ConversionError;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+// AmneziaWG's junk packets (Jc, Jmin, Jmax), handshake padding (S1, S2) and
+// custom message headers (H1-H4) layered on top of plain WireGuard.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct AmneziaConfig {
+    pub jc: Option<u32>,
+    pub jmin: Option<u32>,
+    pub jmax: Option<u32>,
+    pub s1: Option<u32>,
+    pub s2: Option<u32>,
+    pub h1: Option<u32>,
+    pub h2: Option<u32>,
+    pub h3: Option<u32>,
+    pub h4: Option<u32>,
+}
+
+impl AmneziaConfig {
+    // Picks the parameters out of link query or .conf pairs; keys match
+    // case-insensitively since .conf files write `Jc` and links `jc`. Returns
+    // None when none are present, i.e. for plain WireGuard.
+    pub fn from_params(params: &HashMap<String, String>) -> Result<Option<Self>, ConversionError> {
+        let mut config = Self::default();
+        let mut found = false;
+        for (key, value) in params {
+            let key = key.to_lowercase();
+            if let Some(field) = config.field_mut(&key) {
+                let value = value.trim().parse().map_err(|_| {
+                    ConversionError::ParseError(format!("invalid AmneziaWG {}: {}", key, value))
+                })?;
+                *field = Some(value);
+                found = true;
+            }
+        }
+        Ok(found.then_some(config))
+    }
+
+    // The set parameters in canonical order, with lowercase keys.
+    pub fn to_params(&self) -> Vec<(&'static str, u32)> {
+        [
+            ("jc", self.jc),
+            ("jmin", self.jmin),
+            ("jmax", self.jmax),
+            ("s1", self.s1),
+            ("s2", self.s2),
+            ("h1", self.h1),
+            ("h2", self.h2),
+            ("h3", self.h3),
+            ("h4", self.h4),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| value.map(|value| (key, value)))
+        .collect()
+    }
+
+    // mihomo's `amnezia-wg-option` map.
+    pub fn to_clash_option(&self) -> Value {
+        self.to_params()
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), Value::from(value)))
+            .collect::<Map<String, Value>>()
+            .into()
+    }
+
+    fn field_mut(&mut self, key: &str) -> Option<&mut Option<u32>> {
+        match key {
+            "jc" => Some(&mut self.jc),
+            "jmin" => Some(&mut self.jmin),
+            "jmax" => Some(&mut self.jmax),
+            "s1" => Some(&mut self.s1),
+            "s2" => Some(&mut self.s2),
+            "h1" => Some(&mut self.h1),
+            "h2" => Some(&mut self.h2),
+            "h3" => Some(&mut self.h3),
+            "h4" => Some(&mut self.h4),
+            _ => None,
+        }
+    }
+}
+
+// A plain WireGuard client cannot talk to an AmneziaWG server, so targets that
+// lack the extension refuse the node rather than drop the parameters.
+pub(crate) fn unsupported(target: &str) -> ConversionError {
+    ConversionError::UnsupportedFeature(format!(
+        "{} does not support AmneziaWG obfuscation (Jc, Jmin, Jmax, S1, S2, H1-H4)",
+        target
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::Protocol;
+    use semver::Version;
+
+    fn params(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn amnezia_node() -> Protocol {
+        Protocol::parse_uri(
+            "wireguard://priv@198.51.100.1:51820?publickey=pub&ip=10.0.0.2/32&jc=4&jmin=40&jmax=70&h1=1",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn parameters_are_read_case_insensitively() {
+        let config = AmneziaConfig::from_params(&params(&[
+            ("Jc", "4"),
+            ("jmin", " 40 "),
+            ("JMAX", "70"),
+            ("S1", "15"),
+            ("H4", "4"),
+            ("PrivateKey", "ignored"),
+        ]))
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            config,
+            AmneziaConfig {
+                jc: Some(4),
+                jmin: Some(40),
+                jmax: Some(70),
+                s1: Some(15),
+                h4: Some(4),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            config.to_params(),
+            vec![("jc", 4), ("jmin", 40), ("jmax", 70), ("s1", 15), ("h4", 4)]
+        );
+        assert_eq!(
+            config.to_clash_option(),
+            serde_json::json!({ "jc": 4, "jmin": 40, "jmax": 70, "s1": 15, "h4": 4 })
+        );
+    }
+
+    #[test]
+    fn plain_wireguard_has_no_amnezia_config() {
+        let plain = params(&[("publickey", "pub"), ("mtu", "1280")]);
+        assert_eq!(AmneziaConfig::from_params(&plain).unwrap(), None);
+    }
+
+    #[test]
+    fn bad_values_are_errors() {
+        for value in ["four", "-1", "4294967296"] {
+            assert!(matches!(
+                AmneziaConfig::from_params(&params(&[("jc", value)])),
+                Err(ConversionError::ParseError(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn links_carry_the_parameters() {
+        let Protocol::Wireguard { amnezia, .. } = amnezia_node() else {
+            panic!("expected WireGuard");
+        };
+        let amnezia = amnezia.unwrap();
+        assert_eq!(
+            (amnezia.jc, amnezia.h1, amnezia.s1),
+            (Some(4), Some(1), None)
+        );
+
+        let uri = amnezia_node().to_uri();
+        assert_eq!(Protocol::parse_uri(&uri).unwrap(), amnezia_node());
+    }
+
+    #[test]
+    fn targets_without_amnezia_refuse_the_node() {
+        for version in [Version::new(1, 10, 0), Version::new(1, 11, 0)] {
+            assert!(matches!(
+                amnezia_node().to_singbox_outbound(&version),
+                Err(ConversionError::UnsupportedFeature(_))
+            ));
+        }
+        assert!(matches!(
+            amnezia_node().to_xray_outbound(),
+            Err(ConversionError::UnsupportedFeature(_))
+        ));
+        assert_eq!(
+            amnezia_node().to_clash_proxy().unwrap()["amnezia-wg-option"]["jmax"],
+            70
+        );
+    }
+}
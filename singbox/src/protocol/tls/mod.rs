@@ -1,18 +1,21 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct RealityConfig {
     pub public_key: String,
     pub short_id: String,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct UTlsConfig {
     pub enabled: bool,
     pub fingerprint: String,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
 pub struct TlsConfig {
     pub enabled: bool,
     pub insecure: bool,
@@ -23,17 +26,26 @@ pub struct TlsConfig {
 }
 
 impl TlsConfig {
+    // The uTLS fingerprint, if uTLS is switched on. Formats without an
+    // enabled flag of their own treat a disabled one as absent.
+    pub fn fingerprint(&self) -> Option<&str> {
+        self.utls
+            .as_ref()
+            .filter(|utls| utls.enabled)
+            .map(|utls| utls.fingerprint.as_str())
+    }
+
     pub fn to_config(&self) -> Value {
         let mut config = json!({
             "enabled": self.enabled,
             "server_name": self.sni,
             "alpn": self.alpn,
-            "insecure": false,
+            "insecure": self.insecure,
         });
 
         if let Some(utls) = &self.utls {
             config["utls"] = json!({
-                "enabled": true,
+                "enabled": utls.enabled,
                 "fingerprint": utls.fingerprint,
             });
         }
@@ -48,4 +60,43 @@ impl TlsConfig {
 
         config
     }
+
+    // The security half of Xray's `streamSettings`.
+    pub fn to_xray_config(&self) -> Value {
+        if !self.enabled {
+            return json!({ "security": "none" });
+        }
+
+        let fingerprint = self.fingerprint().map(String::from);
+
+        if let Some(reality) = &self.reality {
+            return json!({
+                "security": "reality",
+                "realitySettings": {
+                    "serverName": self.sni.clone().unwrap_or_default(),
+                    "fingerprint": fingerprint.unwrap_or_else(|| "chrome".to_string()),
+                    "publicKey": reality.public_key,
+                    "shortId": reality.short_id,
+                }
+            });
+        }
+
+        let mut settings = json!({
+            "allowInsecure": self.insecure,
+        });
+        if let Some(sni) = &self.sni {
+            settings["serverName"] = json!(sni);
+        }
+        if !self.alpn.is_empty() {
+            settings["alpn"] = json!(self.alpn);
+        }
+        if let Some(fingerprint) = fingerprint {
+            settings["fingerprint"] = json!(fingerprint);
+        }
+
+        json!({
+            "security": "tls",
+            "tlsSettings": settings
+        })
+    }
 }
@@ -1,10 +1,11 @@
 use crate::error::ConversionError;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::str::FromStr;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum TransportConfig {
     Tcp,
@@ -13,8 +14,8 @@ pub enum TransportConfig {
         path: String,
         method: String,
         headers: HashMap<String, String>,
-        idle_timeout: String,
-        ping_timeout: String,
+        idle_timeout: Option<String>,
+        ping_timeout: Option<String>,
     },
     Websocket {
         path: String,
@@ -22,14 +23,18 @@ pub enum TransportConfig {
         max_early_data: u32,
         early_data_header_name: String,
     },
-    Quic,
+    Quic {
+        security: String,
+        key: String,
+        header_type: String,
+    },
     Grpc {
         #[serde(rename = "service_name")]
         service_name: String,
         #[serde(rename = "idle_timeout")]
-        idle_timeout: String,
+        idle_timeout: Option<String>,
         #[serde(rename = "ping_timeout")]
-        ping_timeout: String,
+        ping_timeout: Option<String>,
         #[serde(rename = "permit_without_stream")]
         permit_without_stream: bool,
     },
@@ -38,6 +43,61 @@ pub enum TransportConfig {
         path: String,
         headers: HashMap<String, String>,
     },
+    Xhttp {
+        host: String,
+        path: String,
+        mode: String,
+        extra: Option<Value>,
+    },
+    Kcp {
+        seed: Option<String>,
+        header_type: String,
+        mtu: Option<u32>,
+        tti: Option<u32>,
+        uplink_capacity: Option<u32>,
+        downlink_capacity: Option<u32>,
+        congestion: bool,
+    },
+}
+
+// Header obfuscation types shared by Xray's mKCP and QUIC transports.
+pub const HEADER_TYPES: [&str; 6] = ["none", "srtp", "utp", "wechat-video", "dtls", "wireguard"];
+
+// Packet encryption methods of Xray's QUIC transport.
+pub const QUIC_SECURITIES: [&str; 3] = ["none", "aes-128-gcm", "chacha20-poly1305"];
+
+// Upload modes of Xray's XHTTP (SplitHTTP) transport.
+pub const XHTTP_MODES: [&str; 4] = ["auto", "packet-up", "stream-up", "stream-one"];
+
+// Rejects a `headerType` that neither mKCP nor QUIC knows about.
+pub(crate) fn check_header_type(
+    transport: &str,
+    header_type: String,
+) -> Result<String, ConversionError> {
+    check_option(
+        &HEADER_TYPES,
+        &format!("{} headerType", transport),
+        header_type,
+    )
+}
+
+pub(crate) fn check_quic_security(security: String) -> Result<String, ConversionError> {
+    check_option(&QUIC_SECURITIES, "QUIC security", security)
+}
+
+pub(crate) fn check_xhttp_mode(mode: String) -> Result<String, ConversionError> {
+    check_option(&XHTTP_MODES, "XHTTP mode", mode)
+}
+
+fn check_option(allowed: &[&str], option: &str, value: String) -> Result<String, ConversionError> {
+    if allowed.contains(&value.as_str()) {
+        Ok(value)
+    } else {
+        Err(ConversionError::ParseError(format!(
+            "unknown {}: {}",
+            option, value
+        )))
+    }
 }
 
 impl FromStr for TransportConfig {
@@ -50,8 +110,8 @@ impl FromStr for TransportConfig {
                 path: String::new(),
                 method: String::new(),
                 headers: HashMap::new(),
-                idle_timeout: "15s".to_string(),
-                ping_timeout: "15s".to_string(),
+                idle_timeout: Some("15s".to_string()),
+                ping_timeout: Some("15s".to_string()),
             }),
             "ws" | "websocket" => Ok(TransportConfig::Websocket {
                 path: String::new(),
@@ -59,11 +119,15 @@ impl FromStr for TransportConfig {
                 max_early_data: 0,
                 early_data_header_name: String::new(),
             }),
-            "quic" => Ok(TransportConfig::Quic),
+            "quic" => Ok(TransportConfig::Quic {
+                security: "none".to_string(),
+                key: String::new(),
+                header_type: "none".to_string(),
+            }),
             "grpc" => Ok(TransportConfig::Grpc {
                 service_name: String::new(),
-                idle_timeout: "15s".to_string(),
-                ping_timeout: "15s".to_string(),
+                idle_timeout: Some("15s".to_string()),
+                ping_timeout: Some("15s".to_string()),
                 permit_without_stream: false,
             }),
             "httpupgrade" => Ok(TransportConfig::Httpupgrade {
@@ -71,14 +135,29 @@ impl FromStr for TransportConfig {
                 path: String::new(),
                 headers: HashMap::new(),
             }),
+            "xhttp" | "splithttp" => Ok(TransportConfig::Xhttp {
+                host: String::new(),
+                path: String::new(),
+                mode: "auto".to_string(),
+                extra: None,
+            }),
+            "kcp" | "mkcp" => Ok(TransportConfig::Kcp {
+                seed: None,
+                header_type: "none".to_string(),
+                mtu: None,
+                tti: None,
+                uplink_capacity: None,
+                downlink_capacity: None,
+                congestion: false,
+            }),
             _ => Err(ConversionError::InvalidTransportType(s.to_string())),
         }
     }
 }
 
 impl TransportConfig {
-    pub fn to_config(&self) -> Value {
-        match self {
+    pub fn to_config(&self) -> Result<Value, ConversionError> {
+        let config = match self {
             TransportConfig::Tcp => json!({}),
             TransportConfig::Http {
                 host,
@@ -87,15 +166,17 @@ impl TransportConfig {
                 headers,
                 idle_timeout,
                 ping_timeout,
-            } => json!({
-                "type": "http",
-                "host": host,
-                "path": path,
-                "method": method,
-                "headers": headers,
-                "idle_timeout": idle_timeout,
-                "ping_timeout": ping_timeout
-            }),
+            } => with_timeouts(
+                json!({
+                    "type": "http",
+                    "host": host,
+                    "path": path,
+                    "method": method,
+                    "headers": headers,
+                }),
+                idle_timeout,
+                ping_timeout,
+            ),
 
             TransportConfig::Websocket {
                 path,
@@ -110,20 +191,36 @@ impl TransportConfig {
                 "early_data_header_name": early_data_header_name
             }),
 
-            TransportConfig::Quic => json!({ "type": "quic" }),
+            TransportConfig::Quic {
+                security,
+                key,
+                header_type,
+            } => {
+                // sing-box's v2ray QUIC transport has no encryption or header options,
+                // so anything but the defaults would produce a node that cannot connect.
+                if security != "none" || !key.is_empty() || header_type != "none" {
+                    return Err(ConversionError::UnsupportedFeature(format!(
+                        "sing-box QUIC transport does not support quicSecurity={}, key or headerType={}",
+                        security, header_type
+                    )));
+                }
+                json!({ "type": "quic" })
+            }
 
             TransportConfig::Grpc {
                 service_name,
                 idle_timeout,
                 ping_timeout,
                 permit_without_stream,
-            } => json!({
-                "type": "grpc",
-                "service_name": service_name,
-                "idle_timeout": idle_timeout,
-                "ping_timeout": ping_timeout,
-                "permit_without_stream": permit_without_stream
-            }),
+            } => with_timeouts(
+                json!({
+                    "type": "grpc",
+                    "service_name": service_name,
+                    "permit_without_stream": permit_without_stream
+                }),
+                idle_timeout,
+                ping_timeout,
+            ),
 
             TransportConfig::Httpupgrade {
                 host,
@@ -135,6 +232,333 @@ impl TransportConfig {
                 "path": path,
                 "headers": headers
             }),
+
+            TransportConfig::Xhttp { .. } => {
+                return Err(ConversionError::UnsupportedFeature(
+                    "XHTTP (SplitHTTP) transport is Xray-only and has no sing-box equivalent"
+                        .to_string(),
+                ))
+            }
+
+            TransportConfig::Kcp { .. } => {
+                return Err(ConversionError::UnsupportedFeature(
+                    "mKCP transport is Xray-only and has no sing-box equivalent".to_string(),
+                ))
+            }
+        };
+        Ok(config)
+    }
+
+    pub fn to_xray_config(&self) -> Value {
+        match self {
+            TransportConfig::Tcp => json!({ "network": "tcp" }),
+            TransportConfig::Http {
+                host,
+                path,
+                method,
+                headers,
+                ..
+            } => json!({
+                "network": "http",
+                "httpSettings": {
+                    "host": host,
+                    "path": path,
+                    "method": method,
+                    "headers": xray_headers(headers),
+                }
+            }),
+            TransportConfig::Websocket {
+                path,
+                headers,
+                max_early_data,
+                ..
+            } => {
+                // Xray has no early data setting; it reads `?ed=` from the path.
+                let path = if *max_early_data > 0 {
+                    format!("{}?ed={}", path, max_early_data)
+                } else {
+                    path.clone()
+                };
+                json!({
+                    "network": "ws",
+                    "wsSettings": {
+                        "path": path,
+                        "headers": headers,
+                    }
+                })
+            }
+            TransportConfig::Quic {
+                security,
+                key,
+                header_type,
+            } => json!({
+                "network": "quic",
+                "quicSettings": {
+                    "security": security,
+                    "key": key,
+                    "header": {
+                        "type": header_type
+                    }
+                }
+            }),
+            TransportConfig::Grpc {
+                service_name,
+                permit_without_stream,
+                ..
+            } => json!({
+                "network": "grpc",
+                "grpcSettings": {
+                    "serviceName": service_name,
+                    "permit_without_stream": permit_without_stream,
+                }
+            }),
+            TransportConfig::Httpupgrade {
+                host,
+                path,
+                headers,
+            } => json!({
+                "network": "httpupgrade",
+                "httpupgradeSettings": {
+                    "host": host,
+                    "path": path,
+                    "headers": headers,
+                }
+            }),
+            TransportConfig::Xhttp {
+                host,
+                path,
+                mode,
+                extra,
+            } => {
+                let mut config = json!({
+                    "network": "xhttp",
+                    "xhttpSettings": {
+                        "host": host,
+                        "path": path,
+                        "mode": mode,
+                    }
+                });
+                if let Some(extra) = extra {
+                    config["xhttpSettings"]["extra"] = extra.clone();
+                }
+                config
+            }
+            TransportConfig::Kcp {
+                seed,
+                header_type,
+                mtu,
+                tti,
+                uplink_capacity,
+                downlink_capacity,
+                congestion,
+            } => {
+                let mut settings = json!({
+                    "congestion": congestion,
+                    "header": {
+                        "type": header_type
+                    }
+                });
+                if let Some(seed) = seed {
+                    settings["seed"] = json!(seed);
+                }
+                if let Some(mtu) = mtu {
+                    settings["mtu"] = json!(mtu);
+                }
+                if let Some(tti) = tti {
+                    settings["tti"] = json!(tti);
+                }
+                if let Some(uplink) = uplink_capacity {
+                    settings["uplinkCapacity"] = json!(uplink);
+                }
+                if let Some(downlink) = downlink_capacity {
+                    settings["downlinkCapacity"] = json!(downlink);
+                }
+                json!({
+                    "network": "kcp",
+                    "kcpSettings": settings
+                })
+            }
         }
     }
 }
+
+// Unset timeouts are left out so sing-box applies its own defaults.
+fn with_timeouts(
+    mut config: Value,
+    idle_timeout: &Option<String>,
+    ping_timeout: &Option<String>,
+) -> Value {
+    if let Some(idle_timeout) = idle_timeout {
+        config["idle_timeout"] = json!(idle_timeout);
+    }
+    if let Some(ping_timeout) = ping_timeout {
+        config["ping_timeout"] = json!(ping_timeout);
+    }
+    config
+}
+
+// Xray's http transport takes every header as a list of values.
+fn xray_headers(headers: &HashMap<String, String>) -> Value {
+    headers
+        .iter()
+        .map(|(k, v)| (k.clone(), json!([v])))
+        .collect::<serde_json::Map<String, Value>>()
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::Protocol;
+
+    fn transport(query: &str) -> Result<TransportConfig, ConversionError> {
+        match Protocol::parse_uri(&format!("vless://uuid@example.com:443?{}", query))? {
+            Protocol::Vless { transport, .. } => Ok(transport),
+            protocol => panic!("expected vless, got {:?}", protocol),
+        }
+    }
+
+    #[test]
+    fn quic_parameters_are_parsed() {
+        let quic =
+            transport("type=quic&quicSecurity=aes-128-gcm&key=secret&headerType=srtp").unwrap();
+        assert_eq!(
+            quic,
+            TransportConfig::Quic {
+                security: "aes-128-gcm".to_string(),
+                key: "secret".to_string(),
+                header_type: "srtp".to_string(),
+            }
+        );
+        assert_eq!(
+            transport("type=quic").unwrap(),
+            "quic".parse::<TransportConfig>().unwrap()
+        );
+    }
+
+    #[test]
+    fn unknown_quic_parameters_are_rejected() {
+        assert!(transport("type=quic&quicSecurity=aes-256-cfb").is_err());
+        assert!(transport("type=quic&headerType=http").is_err());
+    }
+
+    #[test]
+    fn quic_parameters_reach_xray_only() {
+        let quic =
+            transport("type=quic&quicSecurity=chacha20-poly1305&key=k&headerType=wechat-video")
+                .unwrap();
+        assert_eq!(
+            quic.to_xray_config(),
+            json!({
+                "network": "quic",
+                "quicSettings": {
+                    "security": "chacha20-poly1305",
+                    "key": "k",
+                    "header": { "type": "wechat-video" }
+                }
+            })
+        );
+        assert!(matches!(
+            quic.to_config(),
+            Err(ConversionError::UnsupportedFeature(_))
+        ));
+        assert_eq!(
+            transport("type=quic").unwrap().to_config().unwrap(),
+            json!({ "type": "quic" })
+        );
+    }
+
+    #[test]
+    fn xhttp_parameters_are_parsed() {
+        let xhttp = transport(
+            "type=xhttp&host=cdn.example.com&path=up&mode=stream-one&extra=%7B%22xPaddingBytes%22%3A%22100-1000%22%7D",
+        )
+        .unwrap();
+        assert_eq!(
+            xhttp,
+            TransportConfig::Xhttp {
+                host: "cdn.example.com".to_string(),
+                path: "/up".to_string(),
+                mode: "stream-one".to_string(),
+                extra: Some(json!({ "xPaddingBytes": "100-1000" })),
+            }
+        );
+        let TransportConfig::Xhttp { mode, .. } = transport("type=splithttp").unwrap() else {
+            panic!("splithttp is an alias of xhttp");
+        };
+        assert_eq!(mode, "auto");
+    }
+
+    #[test]
+    fn unknown_xhttp_modes_are_rejected() {
+        assert!(transport("type=xhttp&mode=stream").is_err());
+        assert!(transport("type=xhttp&extra=not-json").is_err());
+    }
+
+    #[test]
+    fn xhttp_reaches_xray_only() {
+        let xhttp = transport("type=xhttp&path=/x&mode=packet-up&extra=%7B%22a%22%3A1%7D").unwrap();
+        assert_eq!(
+            xhttp.to_xray_config(),
+            json!({
+                "network": "xhttp",
+                "xhttpSettings": { "host": "", "path": "/x", "mode": "packet-up", "extra": { "a": 1 } }
+            })
+        );
+        assert!(matches!(
+            xhttp.to_config(),
+            Err(ConversionError::UnsupportedFeature(_))
+        ));
+    }
+
+    #[test]
+    fn kcp_parameters_are_parsed() {
+        let kcp = transport(
+            "type=kcp&seed=s&headerType=wireguard&mtu=1350&tti=20&uplinkCapacity=5&downlinkCapacity=20&congestion=1",
+        )
+        .unwrap();
+        assert_eq!(
+            kcp,
+            TransportConfig::Kcp {
+                seed: Some("s".to_string()),
+                header_type: "wireguard".to_string(),
+                mtu: Some(1350),
+                tti: Some(20),
+                uplink_capacity: Some(5),
+                downlink_capacity: Some(20),
+                congestion: true,
+            }
+        );
+        assert_eq!(
+            transport("type=mkcp").unwrap(),
+            "kcp".parse::<TransportConfig>().unwrap()
+        );
+    }
+
+    #[test]
+    fn bad_kcp_parameters_are_rejected() {
+        assert!(transport("type=kcp&headerType=http").is_err());
+        assert!(transport("type=kcp&mtu=large").is_err());
+    }
+
+    #[test]
+    fn kcp_reaches_xray_only() {
+        let kcp = transport("type=kcp&seed=s&mtu=1350").unwrap();
+        assert_eq!(
+            kcp.to_xray_config(),
+            json!({
+                "network": "kcp",
+                "kcpSettings": {
+                    "congestion": false,
+                    "header": { "type": "none" },
+                    "seed": "s",
+                    "mtu": 1350
+                }
+            })
+        );
+        assert!(matches!(
+            kcp.to_config(),
+            Err(ConversionError::UnsupportedFeature(_))
+        ));
+    }
+}
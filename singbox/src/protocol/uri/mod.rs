@@ -0,0 +1,1044 @@
+use super::tls::TlsConfig;
+use super::transport::TransportConfig;
+use super::Protocol;
+use base64::engine::general_purpose;
+use base64::Engine;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use url::form_urlencoded;
+
+impl Protocol {
+    // Renders the canonical share link for this node. Every field that
+    // `parse_uri` reads is written back, so the link parses to an equal value.
+    pub fn to_uri(&self) -> String {
+        match self {
+            Self::Shadowsocks {
+                method,
+                password,
+                host,
+                port,
+                plugin,
+                plugin_opts,
+                remark,
+            } => {
+                let userinfo =
+                    general_purpose::URL_SAFE_NO_PAD.encode(format!("{}:{}", method, password));
+                let mut uri = format!("ss://{}@{}:{}", userinfo, host, port);
+                if let Some(plugin) = plugin {
+                    let plugin = match plugin_opts {
+                        Some(opts) => format!("{};{}", plugin, opts),
+                        None => plugin.clone(),
+                    };
+                    uri.push_str("/?");
+                    uri.push_str(&encode_query(&[("plugin", plugin)]));
+                }
+                uri + &encode_remark(remark)
+            }
+            Self::Vmess {
+                uuid,
+                host,
+                port,
+                alter_id,
+                security,
+                transport,
+                tls,
+                remark,
+                // The v2rayN link format has no packet encoding field.
+                ..
+            } => {
+                let mut vmess = Map::new();
+                vmess.insert("v".to_string(), json!("2"));
+                vmess.insert("ps".to_string(), json!(remark.clone().unwrap_or_default()));
+                vmess.insert("add".to_string(), json!(host));
+                vmess.insert("port".to_string(), json!(port.to_string()));
+                vmess.insert("id".to_string(), json!(uuid));
+                vmess.insert("aid".to_string(), json!(alter_id));
+                vmess.insert("scy".to_string(), json!(security));
+                if matches!(transport, TransportConfig::Tcp) {
+                    vmess.insert("type".to_string(), json!("none"));
+                }
+
+                // v2rayN JSON renames a few of the share-link query keys, see `parse_vmess`.
+                for (key, value) in stream_params(transport, tls) {
+                    let key = match key {
+                        "type" => "net",
+                        "headerType" => "type",
+                        "security" => "tls",
+                        "seed" | "serviceName" | "key" => "path",
+                        "quicSecurity" => "host",
+                        key => key,
+                    };
+                    let value = match (key, value.as_str()) {
+                        ("tls", "none") => String::new(),
+                        _ => value,
+                    };
+                    vmess.insert(key.to_string(), json!(value));
+                }
+
+                format!(
+                    "vmess://{}",
+                    general_purpose::STANDARD.encode(Value::Object(vmess).to_string())
+                )
+            }
+            Self::Vless {
+                uuid,
+                host,
+                port,
+                flow,
+                packet_encoding,
+                transport,
+                tls,
+                remark,
+            } => {
+                let mut params = vec![("encryption", "none".to_string())];
+                if let Some(flow) = flow {
+                    params.push(("flow", flow.clone()));
+                }
+                if let Some(encoding) = packet_encoding {
+                    params.push(("packetEncoding", encoding.clone()));
+                }
+                params.extend(stream_params(transport, tls));
+
+                format!(
+                    "vless://{}@{}:{}?{}{}",
+                    urlencoding::encode(uuid),
+                    host,
+                    port,
+                    encode_query(&params),
+                    encode_remark(remark)
+                )
+            }
+            Self::Trojan {
+                password,
+                host,
+                port,
+                transport,
+                tls,
+                remark,
+            } => format!(
+                "trojan://{}@{}:{}?{}{}",
+                urlencoding::encode(password),
+                host,
+                port,
+                encode_query(&stream_params(transport, tls)),
+                encode_remark(remark)
+            ),
+            Self::Wireguard {
+                private_key,
+                public_key,
+                endpoint,
+                dns,
+                mtu,
+                ip,
+                pre_shared_key,
+                persistent_keepalive,
+                amnezia,
+                remark,
+            } => {
+                let mut params = vec![("publickey", public_key.clone()), ("ip", ip.clone())];
+                if let Some(pre_shared_key) = pre_shared_key {
+                    params.push(("presharedkey", pre_shared_key.clone()));
+                }
+                if let Some(keepalive) = persistent_keepalive {
+                    params.push(("keepalive", keepalive.to_string()));
+                }
+                if let Some(dns) = dns {
+                    params.push(("dns", dns.clone()));
+                }
+                if let Some(mtu) = mtu {
+                    params.push(("mtu", mtu.to_string()));
+                }
+                if let Some(amnezia) = amnezia {
+                    params.extend(
+                        amnezia
+                            .to_params()
+                            .into_iter()
+                            .map(|(key, value)| (key, value.to_string())),
+                    );
+                }
+
+                format!(
+                    "wireguard://{}@{}?{}{}",
+                    urlencoding::encode(private_key),
+                    endpoint,
+                    encode_query(&params),
+                    encode_remark(remark)
+                )
+            }
+            Self::Hysteria2 {
+                password,
+                host,
+                port,
+                obfs,
+                obfs_password,
+                up_mbps,
+                down_mbps,
+                tls,
+                remark,
+            } => {
+                let mut params = Vec::new();
+                if let Some(obfs) = obfs {
+                    params.push(("obfs", obfs.clone()));
+                }
+                if let Some(obfs_password) = obfs_password {
+                    params.push(("obfs-password", obfs_password.clone()));
+                }
+                if let Some(up) = up_mbps {
+                    params.push(("upmbps", up.to_string()));
+                }
+                if let Some(down) = down_mbps {
+                    params.push(("downmbps", down.to_string()));
+                }
+                params.extend(quic_security_params(tls));
+
+                format!(
+                    "hysteria2://{}@{}:{}?{}{}",
+                    urlencoding::encode(password),
+                    host,
+                    port,
+                    encode_query(&params),
+                    encode_remark(remark)
+                )
+            }
+            Self::Tuic {
+                uuid,
+                password,
+                host,
+                port,
+                congestion_control,
+                udp_relay_mode,
+                tls,
+                remark,
+            } => {
+                let mut params = Vec::new();
+                if let Some(congestion_control) = congestion_control {
+                    params.push(("congestion_control", congestion_control.clone()));
+                }
+                if let Some(udp_relay_mode) = udp_relay_mode {
+                    params.push(("udp_relay_mode", udp_relay_mode.clone()));
+                }
+                params.extend(quic_security_params(tls));
+
+                format!(
+                    "tuic://{}:{}@{}:{}?{}{}",
+                    urlencoding::encode(uuid),
+                    urlencoding::encode(password),
+                    host,
+                    port,
+                    encode_query(&params),
+                    encode_remark(remark)
+                )
+            }
+            Self::ShadowsocksR {
+                method,
+                password,
+                host,
+                port,
+                ssr_protocol,
+                protocol_param,
+                obfs,
+                obfs_param,
+                remark,
+            } => {
+                let encode = |value: &str| general_purpose::URL_SAFE_NO_PAD.encode(value);
+                let host = if host.contains(':') {
+                    format!("[{}]", host)
+                } else {
+                    host.clone()
+                };
+                let mut params = Vec::new();
+                if let Some(obfs_param) = obfs_param {
+                    params.push(format!("obfsparam={}", encode(obfs_param)));
+                }
+                if let Some(protocol_param) = protocol_param {
+                    params.push(format!("protoparam={}", encode(protocol_param)));
+                }
+                if let Some(remark) = remark {
+                    params.push(format!("remarks={}", encode(remark)));
+                }
+                format!(
+                    "ssr://{}",
+                    encode(&format!(
+                        "{}:{}:{}:{}:{}:{}/?{}",
+                        host,
+                        port,
+                        ssr_protocol,
+                        method,
+                        obfs,
+                        encode(password),
+                        params.join("&")
+                    ))
+                )
+            }
+        }
+    }
+}
+
+fn stream_params(transport: &TransportConfig, tls: &TlsConfig) -> Vec<(&'static str, String)> {
+    let mut params = Vec::new();
+
+    match transport {
+        TransportConfig::Tcp => params.push(("type", "tcp".to_string())),
+        TransportConfig::Http {
+            host,
+            path,
+            method,
+            headers,
+            idle_timeout,
+            ping_timeout,
+        } => {
+            params.push(("type", "http".to_string()));
+            if !host.is_empty() {
+                params.push(("host", host.join(",")));
+            }
+            if !path.is_empty() {
+                params.push(("path", path.clone()));
+            }
+            params.push(("method", method.clone()));
+            if !headers.is_empty() {
+                params.push(("headers", encode_headers(headers)));
+            }
+            if let Some(idle_timeout) = idle_timeout {
+                params.push(("idle_timeout", idle_timeout.clone()));
+            }
+            if let Some(ping_timeout) = ping_timeout {
+                params.push(("ping_timeout", ping_timeout.clone()));
+            }
+        }
+        TransportConfig::Websocket {
+            path,
+            headers,
+            max_early_data,
+            early_data_header_name,
+        } => {
+            params.push(("type", "ws".to_string()));
+            if !path.is_empty() {
+                params.push(("path", path.clone()));
+            }
+            let mut headers = headers.clone();
+            if let Some(host) = headers.remove("Host") {
+                params.push(("host", host));
+            }
+            if !headers.is_empty() {
+                params.push(("headers", encode_headers(&headers)));
+            }
+            if *max_early_data != 0 {
+                params.push(("max_early_data", max_early_data.to_string()));
+            }
+            if !early_data_header_name.is_empty() {
+                params.push(("early_data_header_name", early_data_header_name.clone()));
+            }
+        }
+        TransportConfig::Quic {
+            security,
+            key,
+            header_type,
+        } => {
+            params.push(("type", "quic".to_string()));
+            params.push(("quicSecurity", security.clone()));
+            if !key.is_empty() {
+                params.push(("key", key.clone()));
+            }
+            params.push(("headerType", header_type.clone()));
+        }
+        TransportConfig::Grpc {
+            service_name,
+            idle_timeout,
+            ping_timeout,
+            permit_without_stream,
+        } => {
+            params.push(("type", "grpc".to_string()));
+            if !service_name.is_empty() {
+                params.push(("serviceName", service_name.clone()));
+            }
+            if let Some(idle_timeout) = idle_timeout {
+                params.push(("idle_timeout", idle_timeout.clone()));
+            }
+            if let Some(ping_timeout) = ping_timeout {
+                params.push(("ping_timeout", ping_timeout.clone()));
+            }
+            if *permit_without_stream {
+                params.push(("permit_without_stream", "true".to_string()));
+            }
+        }
+        TransportConfig::Httpupgrade {
+            host,
+            path,
+            headers,
+        } => {
+            params.push(("type", "httpupgrade".to_string()));
+            if !host.is_empty() {
+                params.push(("host", host.clone()));
+            }
+            if !path.is_empty() {
+                params.push(("path", path.clone()));
+            }
+            if !headers.is_empty() {
+                params.push(("headers", encode_headers(headers)));
+            }
+        }
+        TransportConfig::Xhttp {
+            host,
+            path,
+            mode,
+            extra,
+        } => {
+            params.push(("type", "xhttp".to_string()));
+            if !host.is_empty() {
+                params.push(("host", host.clone()));
+            }
+            params.push(("path", path.clone()));
+            params.push(("mode", mode.clone()));
+            if let Some(extra) = extra {
+                params.push(("extra", extra.to_string()));
+            }
+        }
+        TransportConfig::Kcp {
+            seed,
+            header_type,
+            mtu,
+            tti,
+            uplink_capacity,
+            downlink_capacity,
+            congestion,
+        } => {
+            params.push(("type", "kcp".to_string()));
+            params.push(("headerType", header_type.clone()));
+            if let Some(seed) = seed {
+                params.push(("seed", seed.clone()));
+            }
+            for (key, value) in [
+                ("mtu", mtu),
+                ("tti", tti),
+                ("uplinkCapacity", uplink_capacity),
+                ("downlinkCapacity", downlink_capacity),
+            ] {
+                if let Some(value) = value {
+                    params.push((key, value.to_string()));
+                }
+            }
+            if *congestion {
+                params.push(("congestion", "true".to_string()));
+            }
+        }
+    }
+
+    params.extend(security_params(tls));
+    params
+}
+
+fn security_params(tls: &TlsConfig) -> Vec<(&'static str, String)> {
+    let mut params = Vec::new();
+    if !tls.enabled {
+        params.push(("security", "none".to_string()));
+        return params;
+    }
+
+    let security = if tls.reality.is_some() {
+        "reality"
+    } else {
+        "tls"
+    };
+    params.push(("security", security.to_string()));
+    if let Some(sni) = &tls.sni {
+        params.push(("sni", sni.clone()));
+    }
+    if !tls.alpn.is_empty() {
+        params.push(("alpn", tls.alpn.join(",")));
+    }
+    // `parse_tls` defaults a missing `fp` to Chrome, so no uTLS is an empty one.
+    params.push(("fp", tls.fingerprint().unwrap_or_default().to_string()));
+    if tls.insecure {
+        params.push(("insecure", "1".to_string()));
+    }
+    if let Some(reality) = &tls.reality {
+        params.push(("pbk", reality.public_key.clone()));
+        params.push(("sid", reality.short_id.clone()));
+    }
+    params
+}
+
+// Hysteria2 and TUIC never run over uTLS.
+fn quic_security_params(tls: &TlsConfig) -> Vec<(&'static str, String)> {
+    security_params(tls)
+        .into_iter()
+        .filter(|(key, _)| *key != "fp")
+        .collect()
+}
+
+fn encode_query(params: &[(&str, String)]) -> String {
+    form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(params)
+        .finish()
+}
+
+// The inverse of `parse_headers`: `key=value` pairs joined by `&`.
+fn encode_headers(headers: &HashMap<String, String>) -> String {
+    let mut pairs = headers
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>();
+    pairs.sort();
+    pairs.join("&")
+}
+
+fn encode_remark(remark: &Option<String>) -> String {
+    match remark {
+        Some(remark) => format!("#{}", urlencoding::encode(remark)),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::amnezia::AmneziaConfig;
+    use crate::protocol::tls::{RealityConfig, UTlsConfig};
+    use crate::protocol::transport::HEADER_TYPES;
+    use proptest::collection::{hash_map, vec};
+    use proptest::prelude::*;
+
+    // Values `parse_uri` keeps as-is: no empty strings where it reads "" as
+    // absent, leading slashes where it adds them, lowercase ALPN.
+    fn word() -> impl Strategy<Value = String> {
+        "[a-zA-Z0-9]{1,12}"
+    }
+
+    fn text() -> impl Strategy<Value = String> {
+        "[a-zA-Z0-9 ._~!$*()+,;:@/?-]{1,24}"
+    }
+
+    fn host() -> impl Strategy<Value = String> {
+        prop_oneof![
+            "[a-z][a-z0-9]{0,10}(\\.[a-z][a-z0-9]{0,10}){1,2}",
+            (1u8..=254, 0u8..=255, 0u8..=255, 1u8..=254)
+                .prop_map(|(a, b, c, d)| format!("{}.{}.{}.{}", a, b, c, d)),
+        ]
+    }
+
+    fn path() -> impl Strategy<Value = String> {
+        "(/[a-zA-Z0-9._-]{0,10}){1,3}"
+    }
+
+    fn headers() -> impl Strategy<Value = HashMap<String, String>> {
+        hash_map("X-[A-Za-z]{1,8}", word(), 0..3)
+    }
+
+    fn remark() -> impl Strategy<Value = Option<String>> {
+        proptest::option::of(text())
+    }
+
+    fn transport() -> impl Strategy<Value = TransportConfig> {
+        let header_type = proptest::sample::select(HEADER_TYPES.to_vec()).prop_map(String::from);
+        prop_oneof![
+            Just(TransportConfig::Tcp),
+            (
+                vec(host(), 0..3),
+                prop_oneof![Just(String::new()), path()],
+                prop_oneof![Just("GET".to_string()), Just("PUT".to_string())],
+                headers(),
+                "[0-9]{1,3}s".prop_map(Some),
+                "[0-9]{1,3}s".prop_map(Some),
+            )
+                .prop_map(
+                    |(host, path, method, headers, idle_timeout, ping_timeout)| {
+                        TransportConfig::Http {
+                            host,
+                            path,
+                            method,
+                            headers,
+                            idle_timeout,
+                            ping_timeout,
+                        }
+                    }
+                ),
+            (
+                prop_oneof![Just(String::new()), path()],
+                headers(),
+                proptest::option::of(host()),
+                0u32..4096,
+                prop_oneof![Just(String::new()), "Sec-[A-Za-z-]{1,12}"],
+            )
+                .prop_map(
+                    |(path, mut headers, host, max_early_data, early_data_header_name)| {
+                        if let Some(host) = host {
+                            headers.insert("Host".to_string(), host);
+                        }
+                        TransportConfig::Websocket {
+                            path,
+                            headers,
+                            max_early_data,
+                            early_data_header_name,
+                        }
+                    }
+                ),
+            (
+                prop_oneof![Just("none".to_string()), Just("aes-128-gcm".to_string())],
+                prop_oneof![Just(String::new()), word()],
+                header_type.clone(),
+            )
+                .prop_map(|(security, key, header_type)| TransportConfig::Quic {
+                    security,
+                    key,
+                    header_type,
+                }),
+            (
+                prop_oneof![Just(String::new()), word()],
+                "[0-9]{1,3}s".prop_map(Some),
+                "[0-9]{1,3}s".prop_map(Some),
+                any::<bool>(),
+            )
+                .prop_map(
+                    |(service_name, idle_timeout, ping_timeout, permit_without_stream)| {
+                        TransportConfig::Grpc {
+                            service_name,
+                            idle_timeout,
+                            ping_timeout,
+                            permit_without_stream,
+                        }
+                    }
+                ),
+            (
+                prop_oneof![Just(String::new()), host()],
+                prop_oneof![Just(String::new()), path()],
+                headers(),
+            )
+                .prop_map(|(host, path, headers)| TransportConfig::Httpupgrade {
+                    host,
+                    path,
+                    headers,
+                }),
+            (
+                prop_oneof![Just(String::new()), host()],
+                path(),
+                prop_oneof![
+                    Just("auto".to_string()),
+                    Just("packet-up".to_string()),
+                    Just("stream-one".to_string())
+                ],
+                proptest::option::of((word(), 0u32..100)),
+            )
+                .prop_map(|(host, path, mode, extra)| TransportConfig::Xhttp {
+                    host,
+                    path,
+                    mode,
+                    extra: extra.map(|(key, value)| json!({ key: value })),
+                }),
+            (
+                proptest::option::of(word()),
+                header_type,
+                proptest::option::of(576u32..1460),
+                proptest::option::of(10u32..100),
+                proptest::option::of(1u32..100),
+                proptest::option::of(1u32..100),
+                any::<bool>(),
+            )
+                .prop_map(
+                    |(seed, header_type, mtu, tti, uplink, downlink, congestion)| {
+                        TransportConfig::Kcp {
+                            seed,
+                            header_type,
+                            mtu,
+                            tti,
+                            uplink_capacity: uplink,
+                            downlink_capacity: downlink,
+                            congestion,
+                        }
+                    }
+                ),
+        ]
+    }
+
+    fn fingerprint() -> impl Strategy<Value = UTlsConfig> {
+        prop_oneof![
+            Just("chrome"),
+            Just("firefox"),
+            Just("safari"),
+            Just("randomized")
+        ]
+        .prop_map(|fingerprint| UTlsConfig {
+            enabled: true,
+            fingerprint: fingerprint.to_string(),
+        })
+    }
+
+    fn tls_fields() -> impl Strategy<Value = TlsConfig> {
+        (
+            proptest::option::of(host()),
+            vec("[a-z0-9/.]{1,8}", 0..3),
+            any::<bool>(),
+        )
+            .prop_map(|(sni, alpn, insecure)| TlsConfig {
+                enabled: true,
+                insecure,
+                sni,
+                alpn,
+                utls: None,
+                reality: None,
+            })
+    }
+
+    // Stream TLS as the share-link parsers produce it: off, TLS with or
+    // without uTLS, or Reality, which always carries a fingerprint.
+    fn stream_tls() -> impl Strategy<Value = TlsConfig> {
+        prop_oneof![
+            Just(TlsConfig::default()),
+            (tls_fields(), proptest::option::of(fingerprint())).prop_map(|(mut tls, utls)| {
+                tls.utls = utls;
+                tls
+            }),
+            (tls_fields(), fingerprint(), word(), "([0-9a-f]{2}){0,8}").prop_map(
+                |(mut tls, utls, public_key, short_id)| {
+                    tls.utls = Some(utls);
+                    tls.reality = Some(RealityConfig {
+                        public_key,
+                        short_id,
+                    });
+                    tls
+                }
+            ),
+        ]
+    }
+
+    fn shadowsocks() -> impl Strategy<Value = Protocol> {
+        (
+            prop_oneof![
+                Just("aes-128-gcm"),
+                Just("chacha20-ietf-poly1305"),
+                Just("2022-blake3-aes-256-gcm")
+            ],
+            text(),
+            host(),
+            1u16..,
+            proptest::option::of((
+                prop_oneof![Just("obfs-local"), Just("v2ray-plugin")],
+                proptest::option::of(
+                    "[a-z]{1,6}=[a-z0-9.]{1,10}(;[a-z]{1,6}=[a-z0-9.]{1,10}){0,2}",
+                ),
+            )),
+            remark(),
+        )
+            .prop_map(|(method, password, host, port, plugin, remark)| {
+                let (plugin, plugin_opts) = match plugin {
+                    Some((plugin, opts)) => (Some(plugin.to_string()), opts),
+                    None => (None, None),
+                };
+                Protocol::Shadowsocks {
+                    method: method.to_string(),
+                    password,
+                    host,
+                    port,
+                    plugin,
+                    plugin_opts,
+                    remark,
+                }
+            })
+    }
+
+    fn vmess() -> impl Strategy<Value = Protocol> {
+        (
+            word(),
+            host(),
+            1u16..,
+            (0u32..64).prop_map(|aid| aid.to_string()),
+            prop_oneof![Just("auto"), Just("aes-128-gcm"), Just("none")],
+            transport(),
+            stream_tls(),
+            remark(),
+        )
+            .prop_map(
+                |(uuid, host, port, alter_id, security, transport, tls, remark)| Protocol::Vmess {
+                    uuid,
+                    host,
+                    port,
+                    alter_id,
+                    security: security.to_string(),
+                    packet_encoding: None,
+                    transport,
+                    tls,
+                    remark,
+                },
+            )
+    }
+
+    fn vless() -> impl Strategy<Value = Protocol> {
+        (
+            word(),
+            host(),
+            1u16..,
+            proptest::option::of(Just("xtls-rprx-vision".to_string())),
+            proptest::option::of(prop_oneof![
+                Just("xudp".to_string()),
+                Just("packetaddr".to_string())
+            ]),
+            transport(),
+            stream_tls(),
+            remark(),
+        )
+            .prop_map(
+                |(uuid, host, port, flow, packet_encoding, transport, tls, remark)| {
+                    Protocol::Vless {
+                        uuid,
+                        host,
+                        port,
+                        flow,
+                        packet_encoding,
+                        transport,
+                        tls,
+                        remark,
+                    }
+                },
+            )
+    }
+
+    fn trojan() -> impl Strategy<Value = Protocol> {
+        (text(), host(), 1u16.., transport(), stream_tls(), remark()).prop_map(
+            |(password, host, port, transport, tls, remark)| Protocol::Trojan {
+                password,
+                host,
+                port,
+                transport,
+                tls,
+                remark,
+            },
+        )
+    }
+
+    fn amnezia() -> impl Strategy<Value = AmneziaConfig> {
+        (
+            proptest::option::of(1u32..128),
+            proptest::option::of(0u32..1280),
+            proptest::option::of(0u32..1280),
+            1u32..,
+        )
+            .prop_map(|(jc, jmin, jmax, h1)| AmneziaConfig {
+                jc,
+                jmin,
+                jmax,
+                h1: Some(h1),
+                ..AmneziaConfig::default()
+            })
+    }
+
+    fn wireguard() -> impl Strategy<Value = Protocol> {
+        (
+            ("[A-Za-z0-9+/]{43}=", "[A-Za-z0-9+/]{43}="),
+            (host(), 1u16..).prop_map(|(host, port)| format!("{}:{}", host, port)),
+            proptest::option::of("1\\.1\\.1\\.1(,8\\.8\\.8\\.8)?"),
+            proptest::option::of(1280u16..1500),
+            "10\\.0\\.0\\.[0-9]{1,2}/32(,fd00::[0-9]{1,2}/128)?",
+            proptest::option::of("[A-Za-z0-9+/]{43}="),
+            proptest::option::of(1u16..120),
+            proptest::option::of(amnezia()),
+            remark(),
+        )
+            .prop_map(
+                |(
+                    (private_key, public_key),
+                    endpoint,
+                    dns,
+                    mtu,
+                    ip,
+                    pre_shared_key,
+                    persistent_keepalive,
+                    amnezia,
+                    remark,
+                )| Protocol::Wireguard {
+                    private_key,
+                    public_key,
+                    endpoint,
+                    dns,
+                    mtu,
+                    ip,
+                    pre_shared_key,
+                    persistent_keepalive,
+                    amnezia,
+                    remark,
+                },
+            )
+    }
+
+    fn hysteria2() -> impl Strategy<Value = Protocol> {
+        (
+            text(),
+            host(),
+            1u16..,
+            proptest::option::of((Just("salamander".to_string()), proptest::option::of(word()))),
+            proptest::option::of(1u32..1000),
+            proptest::option::of(1u32..1000),
+            tls_fields(),
+            remark(),
+        )
+            .prop_map(
+                |(password, host, port, obfs, up_mbps, down_mbps, tls, remark)| {
+                    let (obfs, obfs_password) = match obfs {
+                        Some((obfs, password)) => (Some(obfs), password),
+                        None => (None, None),
+                    };
+                    Protocol::Hysteria2 {
+                        password,
+                        host,
+                        port,
+                        obfs,
+                        obfs_password,
+                        up_mbps,
+                        down_mbps,
+                        tls,
+                        remark,
+                    }
+                },
+            )
+    }
+
+    fn tuic() -> impl Strategy<Value = Protocol> {
+        (
+            word(),
+            text(),
+            host(),
+            1u16..,
+            proptest::option::of(prop_oneof![
+                Just("bbr".to_string()),
+                Just("cubic".to_string())
+            ]),
+            proptest::option::of(prop_oneof![
+                Just("native".to_string()),
+                Just("quic".to_string())
+            ]),
+            tls_fields(),
+            remark(),
+        )
+            .prop_map(
+                |(uuid, password, host, port, congestion_control, udp_relay_mode, tls, remark)| {
+                    Protocol::Tuic {
+                        uuid,
+                        password,
+                        host,
+                        port,
+                        congestion_control,
+                        udp_relay_mode,
+                        tls,
+                        remark,
+                    }
+                },
+            )
+    }
+
+    fn shadowsocksr() -> impl Strategy<Value = Protocol> {
+        (
+            prop_oneof![Just("aes-256-cfb"), Just("chacha20"), Just("none")],
+            text(),
+            host(),
+            1u16..,
+            prop_oneof![Just("origin"), Just("auth_aes128_md5")],
+            proptest::option::of(text()),
+            prop_oneof![
+                Just("plain"),
+                Just("http_simple"),
+                Just("tls1.2_ticket_auth")
+            ],
+            proptest::option::of(text()),
+            remark(),
+        )
+            .prop_map(
+                |(
+                    method,
+                    password,
+                    host,
+                    port,
+                    ssr_protocol,
+                    protocol_param,
+                    obfs,
+                    obfs_param,
+                    remark,
+                )| Protocol::ShadowsocksR {
+                    method: method.to_string(),
+                    password,
+                    host,
+                    port,
+                    ssr_protocol: ssr_protocol.to_string(),
+                    protocol_param,
+                    obfs: obfs.to_string(),
+                    obfs_param,
+                    remark,
+                },
+            )
+    }
+
+    fn assert_round_trip(protocol: Protocol) -> Result<(), TestCaseError> {
+        let uri = protocol.to_uri();
+        let parsed = Protocol::parse_uri(&uri)
+            .map_err(|e| TestCaseError::fail(format!("{}: {}", uri, e)))?;
+        prop_assert_eq!(parsed, protocol, "{}", uri);
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn shadowsocks_round_trips(protocol in shadowsocks()) {
+            assert_round_trip(protocol)?;
+        }
+
+        #[test]
+        fn vmess_round_trips(protocol in vmess()) {
+            assert_round_trip(protocol)?;
+        }
+
+        #[test]
+        fn vless_round_trips(protocol in vless()) {
+            assert_round_trip(protocol)?;
+        }
+
+        #[test]
+        fn trojan_round_trips(protocol in trojan()) {
+            assert_round_trip(protocol)?;
+        }
+
+        #[test]
+        fn wireguard_round_trips(protocol in wireguard()) {
+            assert_round_trip(protocol)?;
+        }
+
+        #[test]
+        fn hysteria2_round_trips(protocol in hysteria2()) {
+            assert_round_trip(protocol)?;
+        }
+
+        #[test]
+        fn tuic_round_trips(protocol in tuic()) {
+            assert_round_trip(protocol)?;
+        }
+
+        #[test]
+        fn shadowsocksr_round_trips(protocol in shadowsocksr()) {
+            assert_round_trip(protocol)?;
+        }
+    }
+
+    #[test]
+    fn vmess_grpc_service_name_is_in_path() {
+        let protocol = Protocol::Vmess {
+            uuid: "uuid".to_string(),
+            host: "example.com".to_string(),
+            port: 443,
+            alter_id: "0".to_string(),
+            security: "auto".to_string(),
+            packet_encoding: None,
+            transport: TransportConfig::Grpc {
+                service_name: "svc".to_string(),
+                idle_timeout: Some("15s".to_string()),
+                ping_timeout: Some("15s".to_string()),
+                permit_without_stream: false,
+            },
+            tls: TlsConfig::default(),
+            remark: None,
+        };
+        let uri = protocol.to_uri();
+        let json = general_purpose::STANDARD
+            .decode(uri.trim_start_matches("vmess://"))
+            .unwrap();
+        let vmess: Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(vmess["net"], "grpc");
+        assert_eq!(vmess["path"], "svc");
+        assert!(vmess.get("serviceName").is_none());
+    }
+}
@@ -0,0 +1,314 @@
+use super::tls::TlsConfig;
+use super::transport::TransportConfig;
+use super::Protocol;
+use crate::error::ConversionError;
+
+const FLOWS: [&str; 2] = ["xtls-rprx-vision", "xtls-rprx-vision-udp443"];
+const PACKET_ENCODINGS: [&str; 2] = ["packetaddr", "xudp"];
+
+impl Protocol {
+    // Catches protocol/transport/security combinations that parse fine but
+    // fail once the client tries to connect.
+    pub fn validate(&self) -> Result<(), ConversionError> {
+        match self {
+            Self::Vmess {
+                packet_encoding,
+                transport,
+                tls,
+                ..
+            } => {
+                validate_packet_encoding(packet_encoding)?;
+                validate_stream(transport, tls)
+            }
+            Self::Vless {
+                flow,
+                packet_encoding,
+                transport,
+                tls,
+                ..
+            } => {
+                if let Some(flow) = flow {
+                    validate_flow(flow, transport, tls)?;
+                }
+                validate_packet_encoding(packet_encoding)?;
+                validate_stream(transport, tls)
+            }
+            Self::Trojan { transport, tls, .. } => {
+                if !tls.enabled {
+                    return Err(ConversionError::TlsRequired(
+                        "Trojan has no encryption of its own and must run over TLS or Reality"
+                            .to_string(),
+                    ));
+                }
+                validate_stream(transport, tls)
+            }
+            Self::Hysteria2 { tls, .. } | Self::Tuic { tls, .. } => {
+                if !tls.enabled {
+                    return Err(ConversionError::TlsRequired(
+                        "Hysteria2 and TUIC are built on QUIC and always need TLS".to_string(),
+                    ));
+                }
+                Ok(())
+            }
+            Self::Shadowsocks { .. } | Self::ShadowsocksR { .. } | Self::Wireguard { .. } => Ok(()),
+        }
+    }
+}
+
+fn validate_packet_encoding(packet_encoding: &Option<String>) -> Result<(), ConversionError> {
+    match packet_encoding {
+        Some(encoding) if !PACKET_ENCODINGS.contains(&encoding.as_str()) => {
+            Err(ConversionError::InvalidPacketEncoding(format!(
+                "{} (expected one of: {})",
+                encoding,
+                PACKET_ENCODINGS.join(", ")
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn validate_flow(
+    flow: &str,
+    transport: &TransportConfig,
+    tls: &TlsConfig,
+) -> Result<(), ConversionError> {
+    if !FLOWS.contains(&flow) {
+        return Err(ConversionError::InvalidFlow(format!(
+            "{} (expected one of: {})",
+            flow,
+            FLOWS.join(", ")
+        )));
+    }
+    if !matches!(transport, TransportConfig::Tcp) {
+        return Err(ConversionError::IncompatibleTransport(format!(
+            "flow {} splices the raw TCP stream and only works with the tcp transport, not {}",
+            flow,
+            transport_name(transport)
+        )));
+    }
+    if !tls.enabled {
+        return Err(ConversionError::TlsRequired(format!(
+            "flow {} needs TLS or Reality security",
+            flow
+        )));
+    }
+    Ok(())
+}
+
+fn validate_stream(transport: &TransportConfig, tls: &TlsConfig) -> Result<(), ConversionError> {
+    if matches!(transport, TransportConfig::Quic { .. }) && !tls.enabled {
+        return Err(ConversionError::TlsRequired(
+            "the QUIC transport always runs over TLS".to_string(),
+        ));
+    }
+
+    if let Some(reality) = &tls.reality {
+        if !matches!(
+            transport,
+            TransportConfig::Tcp
+                | TransportConfig::Grpc { .. }
+                | TransportConfig::Http { .. }
+                | TransportConfig::Xhttp { .. }
+        ) {
+            return Err(ConversionError::IncompatibleTransport(format!(
+                "Reality only supports tcp, grpc, http and xhttp transports, not {}",
+                transport_name(transport)
+            )));
+        }
+        if reality.public_key.is_empty() {
+            return Err(ConversionError::MissingRealityParam("pbk".to_string()));
+        }
+        if tls.sni.as_deref().unwrap_or_default().is_empty() {
+            return Err(ConversionError::MissingRealityParam("sni".to_string()));
+        }
+        if reality.short_id.len() > 16
+            || reality.short_id.len() % 2 != 0
+            || !reality.short_id.chars().all(|c| c.is_ascii_hexdigit())
+        {
+            return Err(ConversionError::InvalidRealityParam(format!(
+                "sid must be an even-length hex string of at most 16 characters, got {}",
+                reality.short_id
+            )));
+        }
+        if !tls.utls.as_ref().is_some_and(|utls| utls.enabled) {
+            return Err(ConversionError::MissingRealityParam(
+                "fp (Reality needs a uTLS fingerprint)".to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn transport_name(transport: &TransportConfig) -> &'static str {
+    match transport {
+        TransportConfig::Tcp => "tcp",
+        TransportConfig::Http { .. } => "http",
+        TransportConfig::Websocket { .. } => "ws",
+        TransportConfig::Quic { .. } => "quic",
+        TransportConfig::Grpc { .. } => "grpc",
+        TransportConfig::Httpupgrade { .. } => "httpupgrade",
+        TransportConfig::Xhttp { .. } => "xhttp",
+        TransportConfig::Kcp { .. } => "kcp",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::tls::{RealityConfig, UTlsConfig};
+
+    fn vless(flow: Option<&str>, transport: TransportConfig, tls: TlsConfig) -> Protocol {
+        Protocol::Vless {
+            uuid: "uuid".to_string(),
+            host: "example.com".to_string(),
+            port: 443,
+            flow: flow.map(String::from),
+            packet_encoding: None,
+            transport,
+            tls,
+            remark: None,
+        }
+    }
+
+    fn tls() -> TlsConfig {
+        TlsConfig {
+            enabled: true,
+            sni: Some("example.com".to_string()),
+            utls: Some(UTlsConfig {
+                enabled: true,
+                fingerprint: "chrome".to_string(),
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn reality(public_key: &str, short_id: &str) -> TlsConfig {
+        TlsConfig {
+            reality: Some(RealityConfig {
+                public_key: public_key.to_string(),
+                short_id: short_id.to_string(),
+            }),
+            ..tls()
+        }
+    }
+
+    fn ws() -> TransportConfig {
+        "ws".parse().unwrap()
+    }
+
+    #[test]
+    fn valid_combinations_pass() {
+        let vision = vless(
+            Some("xtls-rprx-vision"),
+            TransportConfig::Tcp,
+            reality("pbk", "ab12"),
+        );
+        assert!(vision.validate().is_ok());
+        assert!(vless(None, ws(), tls()).validate().is_ok());
+    }
+
+    #[test]
+    fn unknown_flow_is_invalid_flow() {
+        let protocol = vless(Some("xtls-rprx-direct"), TransportConfig::Tcp, tls());
+        assert!(matches!(
+            protocol.validate(),
+            Err(ConversionError::InvalidFlow(_))
+        ));
+    }
+
+    #[test]
+    fn unknown_packet_encoding_is_invalid_packet_encoding() {
+        let mut protocol = vless(None, TransportConfig::Tcp, tls());
+        if let Protocol::Vless {
+            packet_encoding, ..
+        } = &mut protocol
+        {
+            *packet_encoding = Some("udp".to_string());
+        }
+        assert!(matches!(
+            protocol.validate(),
+            Err(ConversionError::InvalidPacketEncoding(_))
+        ));
+    }
+
+    #[test]
+    fn flow_or_reality_over_ws_is_incompatible_transport() {
+        let flow = vless(Some("xtls-rprx-vision"), ws(), tls());
+        assert!(matches!(
+            flow.validate(),
+            Err(ConversionError::IncompatibleTransport(_))
+        ));
+        let reality = vless(None, ws(), reality("pbk", ""));
+        assert!(matches!(
+            reality.validate(),
+            Err(ConversionError::IncompatibleTransport(_))
+        ));
+    }
+
+    #[test]
+    fn plaintext_trojan_flow_or_quic_is_tls_required() {
+        let trojan = Protocol::Trojan {
+            password: "secret".to_string(),
+            host: "example.com".to_string(),
+            port: 443,
+            transport: TransportConfig::Tcp,
+            tls: TlsConfig::default(),
+            remark: None,
+        };
+        assert!(matches!(
+            trojan.validate(),
+            Err(ConversionError::TlsRequired(_))
+        ));
+        let flow = vless(
+            Some("xtls-rprx-vision"),
+            TransportConfig::Tcp,
+            TlsConfig::default(),
+        );
+        assert!(matches!(
+            flow.validate(),
+            Err(ConversionError::TlsRequired(_))
+        ));
+        let quic = vless(None, "quic".parse().unwrap(), TlsConfig::default());
+        assert!(matches!(
+            quic.validate(),
+            Err(ConversionError::TlsRequired(_))
+        ));
+    }
+
+    #[test]
+    fn absent_reality_parameters_are_missing_reality_param() {
+        let no_key = vless(None, TransportConfig::Tcp, reality("", ""));
+        assert!(matches!(
+            no_key.validate(),
+            Err(ConversionError::MissingRealityParam(_))
+        ));
+
+        let mut no_sni = reality("pbk", "");
+        no_sni.sni = None;
+        let no_sni = vless(None, TransportConfig::Tcp, no_sni);
+        assert!(matches!(
+            no_sni.validate(),
+            Err(ConversionError::MissingRealityParam(_))
+        ));
+
+        let mut no_fp = reality("pbk", "");
+        no_fp.utls = None;
+        let no_fp = vless(None, TransportConfig::Tcp, no_fp);
+        assert!(matches!(
+            no_fp.validate(),
+            Err(ConversionError::MissingRealityParam(_))
+        ));
+    }
+
+    #[test]
+    fn malformed_short_id_is_invalid_reality_param() {
+        for short_id in ["abc", "xyz0", "0123456789abcdef01"] {
+            let protocol = vless(None, TransportConfig::Tcp, reality("pbk", short_id));
+            assert!(matches!(
+                protocol.validate(),
+                Err(ConversionError::InvalidRealityParam(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use crate::error::ConversionError;
+use crate::protocol::Protocol;
+use qrcode::render::{svg, unicode};
+use qrcode::types::QrError;
+use qrcode::{Color, EcLevel};
+use std::fs;
+use std::path::Path;
+
+// Modules of blank border the QR spec asks for around the symbol.
+const QUIET_ZONE: usize = 4;
+
+// A QR code for a share link, deep link or small config.
+pub struct QrCode {
+    code: qrcode::QrCode,
+}
+
+impl QrCode {
+    // Encodes `payload` in the smallest symbol that fits it, then raises the
+    // error correction as far as that symbol size allows, so scannability
+    // improves without the code growing.
+    pub fn new(payload: &str) -> Result<Self, ConversionError> {
+        let smallest = qrcode::QrCode::with_error_correction_level(payload, EcLevel::L)
+            .map_err(|e| qr_error(e, payload))?;
+        let version = smallest.version();
+
+        let code = [EcLevel::H, EcLevel::Q, EcLevel::M]
+            .into_iter()
+            .find_map(|level| qrcode::QrCode::with_version(payload, version, level).ok())
+            .unwrap_or(smallest);
+        Ok(Self { code })
+    }
+
+    pub fn from_protocol(protocol: &Protocol) -> Result<Self, ConversionError> {
+        Self::new(&protocol.to_uri())
+    }
+
+    // "L", "M", "Q" or "H".
+    pub fn error_correction(&self) -> &'static str {
+        match self.code.error_correction_level() {
+            EcLevel::L => "L",
+            EcLevel::M => "M",
+            EcLevel::Q => "Q",
+            EcLevel::H => "H",
+        }
+    }
+
+    // Modules per side, without the quiet zone.
+    pub fn width(&self) -> usize {
+        self.code.width()
+    }
+
+    // A grayscale PNG with `scale` pixels per module.
+    pub fn to_png(&self, scale: u32) -> Result<Vec<u8>, ConversionError> {
+        let scale = scale.max(1) as usize;
+        let modules = self.width() + 2 * QUIET_ZONE;
+        let size = modules * scale;
+        let colors = self.code.to_colors();
+
+        let mut pixels = vec![255u8; size * size];
+        for (index, color) in colors.iter().enumerate() {
+            if *color == Color::Light {
+                continue;
+            }
+            let (x, y) = (
+                index % self.width() + QUIET_ZONE,
+                index / self.width() + QUIET_ZONE,
+            );
+            for row in y * scale..(y + 1) * scale {
+                pixels[row * size + x * scale..row * size + (x + 1) * scale].fill(0);
+            }
+        }
+
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, size as u32, size as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&pixels))
+            .map_err(|e| ConversionError::SerializationError(e.to_string()))?;
+        Ok(png)
+    }
+
+    pub fn to_svg(&self) -> String {
+        self.code
+            .render::<svg::Color>()
+            .min_dimensions(256, 256)
+            .dark_color(svg::Color("#000000"))
+            .light_color(svg::Color("#ffffff"))
+            .build()
+    }
+
+    // Half-height block characters, two modules per line. Colours are
+    // inverted for the usual light-on-dark terminal.
+    pub fn to_terminal(&self) -> String {
+        self.code
+            .render::<unicode::Dense1x2>()
+            .dark_color(unicode::Dense1x2::Light)
+            .light_color(unicode::Dense1x2::Dark)
+            .build()
+    }
+
+    pub fn save_png(&self, filename: &str, scale: u32) -> Result<(), ConversionError> {
+        fs::write(Path::new(filename), self.to_png(scale)?)
+            .map_err(|e| ConversionError::IoError(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+// Deep link that makes the sing-box apps (SFA, SFI, SFM) import `url` as a
+// remote profile named `name`.
+pub fn remote_profile_link(url: &str, name: &str) -> String {
+    format!(
+        "sing-box://import-remote-profile?url={}#{}",
+        urlencoding::encode(url),
+        urlencoding::encode(name)
+    )
+}
+
+fn qr_error(error: QrError, payload: &str) -> ConversionError {
+    match error {
+        QrError::DataTooLong => ConversionError::PayloadTooLarge(payload.len()),
+        error => ConversionError::SerializationError(error.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_png(png: &[u8]) -> (u32, Vec<u8>) {
+        let decoder = png::Decoder::new(png);
+        let mut reader = decoder.read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!(info.width, info.height);
+        assert_eq!(info.color_type, png::ColorType::Grayscale);
+        (info.width, pixels)
+    }
+
+    #[test]
+    fn error_correction_is_raised_without_growing_the_symbol() {
+        for payload in [
+            "hi".to_string(),
+            "trojan://secret@example.com:443?security=tls&type=tcp#Node".to_string(),
+            "x".repeat(300),
+        ] {
+            let smallest =
+                qrcode::QrCode::with_error_correction_level(payload.as_str(), EcLevel::L).unwrap();
+            let code = QrCode::new(&payload).unwrap();
+            assert_eq!(code.width(), smallest.width(), "{}", payload);
+
+            let chosen = code.code.error_correction_level();
+            let higher = [EcLevel::H, EcLevel::Q, EcLevel::M]
+                .into_iter()
+                .take_while(|level| *level != chosen);
+            for level in higher {
+                assert!(
+                    qrcode::QrCode::with_version(payload.as_str(), smallest.version(), level)
+                        .is_err()
+                );
+            }
+        }
+        assert_eq!(QrCode::new("hi").unwrap().error_correction(), "H");
+    }
+
+    #[test]
+    fn oversized_payloads_are_reported() {
+        assert!(matches!(
+            QrCode::new(&"x".repeat(8000)),
+            Err(ConversionError::PayloadTooLarge(8000))
+        ));
+    }
+
+    #[test]
+    fn png_has_the_quiet_zone_and_scale() {
+        let code = QrCode::new("hi").unwrap();
+        let scale = 3;
+        let (size, pixels) = decode_png(&code.to_png(scale).unwrap());
+        let size = size as usize;
+        assert_eq!(size, (code.width() + 2 * QUIET_ZONE) * scale as usize);
+
+        let pixel = |x: usize, y: usize| pixels[y * size + x];
+        let border = QUIET_ZONE * scale as usize;
+        for i in 0..size {
+            for j in 0..border {
+                assert_eq!(pixel(i, j), 255);
+                assert_eq!(pixel(j, i), 255);
+                assert_eq!(pixel(i, size - 1 - j), 255);
+                assert_eq!(pixel(size - 1 - j, i), 255);
+            }
+        }
+        // The top-left finder pattern starts right after the quiet zone.
+        assert_eq!(pixel(border, border), 0);
+
+        let (unscaled, _) = decode_png(&code.to_png(0).unwrap());
+        assert_eq!(unscaled as usize, code.width() + 2 * QUIET_ZONE);
+    }
+
+    #[test]
+    fn deep_link_encodes_url_and_name() {
+        assert_eq!(
+            remote_profile_link("https://example.com/sub?token=a&b=c", "My Sub"),
+            "sing-box://import-remote-profile?url=https%3A%2F%2Fexample.com%2Fsub%3Ftoken%3Da%26b%3Dc#My%20Sub"
+        );
+    }
+}
@@ -0,0 +1,322 @@
+use crate::error::ConversionError;
+use crate::profile::{profile_sections, split_fields, split_options};
+use crate::protocol::tls::{RealityConfig, TlsConfig, UTlsConfig};
+use crate::protocol::transport::TransportConfig;
+use crate::protocol::Protocol;
+use std::collections::HashMap;
+
+// Reads the `[server_local]` section of a Quantumult X profile, or a bare list
+// of server lines such as a Quantumult X server subscription. Server types we
+// do not support and lines we cannot read are skipped.
+pub fn parse_config(content: &str) -> Result<Vec<Protocol>, ConversionError> {
+    let sections = profile_sections(content);
+    let (_, lines) = sections
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("server_local"))
+        .or_else(|| sections.first().filter(|(_, lines)| !lines.is_empty()))
+        .ok_or(ConversionError::MissingField("[server_local]"))?;
+
+    let mut protocols = Vec::new();
+    for line in lines {
+        match parse_server(line) {
+            Ok(protocol) => protocols.push(protocol),
+            Err(ConversionError::UnsupportedProtocol(server_type)) => {
+                log::warn!(
+                    "Skipping unsupported Quantumult X server type: {}",
+                    server_type
+                );
+            }
+            Err(e) => log::warn!("Skipping Quantumult X server line {}: {}", line, e),
+        }
+    }
+    Ok(protocols)
+}
+
+// Parses one `type=host:port, key=value, ..., tag=name` line.
+pub fn parse_server(line: &str) -> Result<Protocol, ConversionError> {
+    let fields = split_fields(line, ',');
+    let (server_type, server) = fields
+        .first()
+        .and_then(|field| field.split_once('='))
+        .ok_or_else(|| ConversionError::ParseError(format!("invalid server line: {}", line)))?;
+    let (host, port) = parse_server_address(server.trim())?;
+    let options = split_options(&fields[1..]).1;
+    let option = |key: &str| options.get(key).filter(|v| !v.is_empty()).cloned();
+    let remark = option("tag");
+
+    match server_type.trim().to_lowercase().as_str() {
+        "shadowsocks" if options.contains_key("ssr-protocol") => Ok(Protocol::ShadowsocksR {
+            method: option("method").ok_or(ConversionError::MissingField("method"))?,
+            password: option("password").ok_or(ConversionError::MissingPassword)?,
+            host,
+            port,
+            ssr_protocol: option("ssr-protocol")
+                .ok_or(ConversionError::MissingField("ssr-protocol"))?,
+            protocol_param: option("ssr-protocol-param"),
+            obfs: option("obfs").unwrap_or_else(|| "plain".to_string()),
+            obfs_param: option("obfs-host"),
+            remark,
+        }),
+        "shadowsocks" => {
+            let (plugin, plugin_opts) = parse_plugin(&options)?;
+            Ok(Protocol::Shadowsocks {
+                method: option("method").ok_or(ConversionError::MissingField("method"))?,
+                password: option("password").ok_or(ConversionError::MissingPassword)?,
+                host,
+                port,
+                plugin,
+                plugin_opts,
+                remark,
+            })
+        }
+        "vmess" => {
+            let (transport, tls) = parse_obfs(&options)?;
+            Ok(Protocol::Vmess {
+                uuid: option("password").ok_or(ConversionError::MissingUUID)?,
+                host,
+                port,
+                alter_id: "0".to_string(),
+                security: option("method").unwrap_or_else(|| "auto".to_string()),
+                packet_encoding: None,
+                transport,
+                tls,
+                remark,
+            })
+        }
+        "vless" => {
+            let (transport, tls) = parse_obfs(&options)?;
+            Ok(Protocol::Vless {
+                uuid: option("password").ok_or(ConversionError::MissingUUID)?,
+                host,
+                port,
+                flow: option("vless-flow"),
+                packet_encoding: None,
+                transport,
+                tls,
+                remark,
+            })
+        }
+        "trojan" => {
+            let (transport, mut tls) = parse_obfs(&options)?;
+            // `over-tls=true` and `obfs=wss` are the only forms; TLS is implied.
+            tls.enabled = true;
+            Ok(Protocol::Trojan {
+                password: option("password").ok_or(ConversionError::MissingPassword)?,
+                host,
+                port,
+                transport,
+                tls,
+                remark,
+            })
+        }
+        server_type => Err(ConversionError::UnsupportedProtocol(
+            server_type.to_string(),
+        )),
+    }
+}
+
+// Quantumult X folds transport and TLS into `obfs`: over-tls, ws, wss or http.
+fn parse_obfs(
+    options: &HashMap<String, String>,
+) -> Result<(TransportConfig, TlsConfig), ConversionError> {
+    let option = |key: &str| options.get(key).filter(|v| !v.is_empty()).cloned();
+    let obfs_host = option("obfs-host");
+    let path = option("obfs-uri").unwrap_or_default();
+
+    let obfs = option("obfs").or_else(|| {
+        options
+            .get("over-tls")
+            .is_some_and(|v| v == "true")
+            .then(|| "over-tls".to_string())
+    });
+    let (transport, tls) = match obfs.as_deref() {
+        None => (TransportConfig::Tcp, false),
+        Some("over-tls") => (TransportConfig::Tcp, true),
+        Some(obfs @ ("ws" | "wss")) => (
+            TransportConfig::Websocket {
+                path,
+                headers: obfs_host
+                    .clone()
+                    .map(|host| HashMap::from([("Host".to_string(), host)]))
+                    .unwrap_or_default(),
+                max_early_data: 0,
+                early_data_header_name: String::new(),
+            },
+            obfs == "wss",
+        ),
+        Some("http") => (
+            TransportConfig::Http {
+                host: obfs_host.clone().into_iter().collect(),
+                path: if path.is_empty() {
+                    "/".to_string()
+                } else {
+                    path
+                },
+                method: "GET".to_string(),
+                headers: HashMap::new(),
+                idle_timeout: Some("15s".to_string()),
+                ping_timeout: Some("15s".to_string()),
+            },
+            false,
+        ),
+        Some(obfs) => return Err(ConversionError::InvalidTransportType(obfs.to_string())),
+    };
+
+    let reality = option("reality-base64-pubkey").map(|public_key| RealityConfig {
+        public_key,
+        short_id: option("reality-hex-shortid").unwrap_or_default(),
+    });
+    Ok((
+        transport,
+        TlsConfig {
+            enabled: tls || reality.is_some(),
+            insecure: options
+                .get("tls-verification")
+                .is_some_and(|v| v == "false"),
+            sni: option("tls-host").or(obfs_host),
+            utls: reality.as_ref().map(|_| UTlsConfig {
+                enabled: true,
+                fingerprint: "chrome".to_string(),
+            }),
+            reality,
+            ..Default::default()
+        },
+    ))
+}
+
+// `obfs=http|tls` is simple-obfs; `obfs=ws|wss` is v2ray-plugin's websocket mode.
+fn parse_plugin(
+    options: &HashMap<String, String>,
+) -> Result<(Option<String>, Option<String>), ConversionError> {
+    let Some(obfs) = options.get("obfs") else {
+        return Ok((None, None));
+    };
+    let host = options.get("obfs-host");
+    let (plugin, pairs) = match obfs.as_str() {
+        "http" | "tls" => (
+            "obfs-local",
+            vec![
+                Some(format!("obfs={}", obfs)),
+                host.map(|host| format!("obfs-host={}", host)),
+            ],
+        ),
+        "ws" | "wss" => (
+            "v2ray-plugin",
+            vec![
+                Some("mode=websocket".to_string()),
+                (obfs == "wss").then(|| "tls".to_string()),
+                host.map(|host| format!("host={}", host)),
+                options.get("obfs-uri").map(|path| format!("path={}", path)),
+            ],
+        ),
+        obfs => return Err(ConversionError::InvalidTransportType(obfs.to_string())),
+    };
+    let opts = pairs.into_iter().flatten().collect::<Vec<_>>().join(";");
+    Ok((Some(plugin.to_string()), Some(opts)))
+}
+
+// `host:port`, with IPv6 hosts in brackets.
+fn parse_server_address(server: &str) -> Result<(String, u16), ConversionError> {
+    let (host, port) = server
+        .rsplit_once(':')
+        .ok_or(ConversionError::MissingPort)?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if host.is_empty() {
+        return Err(ConversionError::MissingHost);
+    }
+    let port = port.parse().map_err(|_| ConversionError::MissingPort)?;
+    Ok((host.to_string(), port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROFILE: &str = r#"
+[general]
+server_check_url=http://www.gstatic.com/generate_204
+
+[server_local]
+shadowsocks=example.com:8388, method=aes-256-gcm, password=secret, obfs=wss, obfs-host=cdn.example.com, obfs-uri=/ws, tag=SS
+shadowsocks=[2001:db8::1]:8388, method=aes-256-cfb, password=secret, ssr-protocol=auth_aes128_md5, ssr-protocol-param=1:pw, obfs=tls1.2_ticket_auth, obfs-host=cdn.example.com, tag=SSR
+vmess=example.com:443, method=chacha20-poly1305, password=uuid, obfs=wss, obfs-host=cdn.example.com, obfs-uri=/ws, aead=true, tag=VMess
+trojan=example.com:443, password=secret, over-tls=true, tls-host=sni.example.com, tls-verification=false, tag=Trojan
+vless=example.com:443, method=none, password=uuid, obfs=over-tls, obfs-host=sni.example.com, reality-base64-pubkey=pbk, reality-hex-shortid=ab12, vless-flow=xtls-rprx-vision, tag=Reality
+http=example.com:8080, tag=HTTP
+trojan=example.com, password=secret, tag=Broken
+"#;
+
+    #[test]
+    fn server_local_is_read() {
+        let protocols = parse_config(PROFILE).unwrap();
+        let names: Vec<_> = protocols.iter().map(|p| p.remark().unwrap()).collect();
+        assert_eq!(names, vec!["SS", "SSR", "VMess", "Trojan", "Reality"]);
+
+        let Protocol::Shadowsocks {
+            plugin,
+            plugin_opts,
+            ..
+        } = &protocols[0]
+        else {
+            panic!("expected Shadowsocks");
+        };
+        assert_eq!(plugin.as_deref(), Some("v2ray-plugin"));
+        assert_eq!(
+            plugin_opts.as_deref(),
+            Some("mode=websocket;tls;host=cdn.example.com;path=/ws")
+        );
+
+        assert_eq!(
+            protocols[1],
+            Protocol::ShadowsocksR {
+                method: "aes-256-cfb".to_string(),
+                password: "secret".to_string(),
+                host: "2001:db8::1".to_string(),
+                port: 8388,
+                ssr_protocol: "auth_aes128_md5".to_string(),
+                protocol_param: Some("1:pw".to_string()),
+                obfs: "tls1.2_ticket_auth".to_string(),
+                obfs_param: Some("cdn.example.com".to_string()),
+                remark: Some("SSR".to_string()),
+            }
+        );
+
+        let Protocol::Vmess { transport, tls, .. } = &protocols[2] else {
+            panic!("expected VMess");
+        };
+        assert!(matches!(transport, TransportConfig::Websocket { path, .. } if path == "/ws"));
+        assert!(tls.enabled);
+        assert_eq!(tls.sni.as_deref(), Some("cdn.example.com"));
+
+        let Protocol::Trojan { tls, .. } = &protocols[3] else {
+            panic!("expected Trojan");
+        };
+        assert!(tls.enabled && tls.insecure);
+        assert_eq!(tls.sni.as_deref(), Some("sni.example.com"));
+
+        let Protocol::Vless { flow, tls, .. } = &protocols[4] else {
+            panic!("expected VLESS");
+        };
+        assert_eq!(flow.as_deref(), Some("xtls-rprx-vision"));
+        assert_eq!(tls.reality.as_ref().unwrap().short_id, "ab12");
+    }
+
+    #[test]
+    fn bare_server_lists_are_read() {
+        let protocols =
+            parse_config("trojan=example.com:443, password=secret, over-tls=true, tag=T").unwrap();
+        assert_eq!(protocols.len(), 1);
+        assert!(matches!(
+            parse_config("[general]\nserver_check_url=x"),
+            Err(ConversionError::MissingField(_))
+        ));
+    }
+
+    #[test]
+    fn exported_lines_read_back() {
+        for protocol in parse_config(PROFILE).unwrap() {
+            let line = protocol.to_quantumult_proxy().unwrap();
+            assert_eq!(parse_server(&line).unwrap(), protocol, "{}", line);
+        }
+    }
+}
@@ -0,0 +1,91 @@
+pub mod import;
+pub mod proxy;
+use crate::profile::with_profile_names;
+use crate::protocol::Protocol;
+
+// A minimal Quantumult X profile: one `static` policy over every node it can
+// carry, Iranian and private destinations direct, everything else proxied.
+// Nodes Quantumult X does not support are skipped.
+pub fn profile(protocols: &[Protocol]) -> String {
+    let mut servers = Vec::new();
+    let mut names = Vec::new();
+    for protocol in with_profile_names(protocols, &[',']) {
+        match protocol.to_quantumult_proxy() {
+            Ok(line) => {
+                names.push(protocol.display_name());
+                servers.push(line);
+            }
+            Err(e) => log::warn!(
+                "Skipping {} in Quantumult X profile: {}",
+                protocol.display_name(),
+                e
+            ),
+        }
+    }
+
+    // An empty group is rejected by the client; offer direct instead.
+    if names.is_empty() {
+        names.push("direct".to_string());
+    }
+
+    let mut profile = vec![
+        "[general]".to_string(),
+        "network_check_url=http://www.gstatic.com/generate_204".to_string(),
+        "server_check_url=http://www.gstatic.com/generate_204".to_string(),
+        String::new(),
+        "[dns]".to_string(),
+        "server=1.1.1.1".to_string(),
+        String::new(),
+        "[policy]".to_string(),
+        format!("static=PROXY, {}", names.join(", ")),
+        String::new(),
+        "[server_local]".to_string(),
+    ];
+    profile.extend(servers);
+    profile.extend([
+        String::new(),
+        "[filter_local]".to_string(),
+        "ip-cidr, 192.168.0.0/16, direct".to_string(),
+        "ip-cidr, 10.0.0.0/8, direct".to_string(),
+        "ip-cidr, 172.16.0.0/12, direct".to_string(),
+        "host-suffix, ir, direct".to_string(),
+        "geoip, ir, direct".to_string(),
+        "final, PROXY".to_string(),
+    ]);
+    profile.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::tls::TlsConfig;
+    use crate::protocol::transport::TransportConfig;
+
+    fn trojan(name: &str) -> Protocol {
+        Protocol::Trojan {
+            password: "secret".to_string(),
+            host: "example.com".to_string(),
+            port: 443,
+            transport: TransportConfig::Tcp,
+            tls: TlsConfig {
+                enabled: true,
+                ..Default::default()
+            },
+            remark: Some(name.to_string()),
+        }
+    }
+
+    #[test]
+    fn names_are_cleaned_before_they_are_made_unique() {
+        let profile = profile(&[trojan("a,b"), trojan("a b")]);
+        let lines: Vec<_> = profile.lines().collect();
+        assert!(lines.contains(&"static=PROXY, a b, a b 2"));
+        assert_eq!(lines.iter().filter(|l| l.ends_with("tag=a b")).count(), 1);
+        assert_eq!(lines.iter().filter(|l| l.ends_with("tag=a b 2")).count(), 1);
+    }
+
+    #[test]
+    fn no_nodes_fall_back_to_direct() {
+        assert!(profile(&[]).lines().any(|l| l == "static=PROXY, direct"));
+    }
+}
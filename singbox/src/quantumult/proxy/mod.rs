@@ -0,0 +1,322 @@
+use crate::error::ConversionError;
+use crate::protocol::tls::TlsConfig;
+use crate::protocol::transport::TransportConfig;
+use crate::protocol::{parse_alter_id, plugin_options, Protocol, SIMPLE_OBFS};
+
+impl Protocol {
+    // A Quantumult X `[server_local]` line: `type=host:port, key=value, ..., tag=name`.
+    pub fn to_quantumult_proxy(&self) -> Result<String, ConversionError> {
+        let name = self.display_name().replace(',', " ");
+        let mut fields = Vec::new();
+
+        match self {
+            Self::Shadowsocks {
+                method,
+                password,
+                host,
+                port,
+                plugin,
+                plugin_opts,
+                ..
+            } => {
+                fields.extend([
+                    format!("shadowsocks={}", server(host, *port)),
+                    format!("method={}", method),
+                    format!("password={}", password),
+                ]);
+                if let Some(plugin) = plugin {
+                    let opts = plugin_options(plugin_opts.as_deref());
+                    let option = |key: &str| opts.get(key).filter(|v| !v.is_empty());
+                    if SIMPLE_OBFS.contains(&plugin.as_str()) {
+                        fields.push(format!(
+                            "obfs={}",
+                            option("obfs").map_or("http", String::as_str)
+                        ));
+                        if let Some(host) = option("obfs-host") {
+                            fields.push(format!("obfs-host={}", host));
+                        }
+                    } else if plugin == "v2ray-plugin"
+                        && option("mode").is_none_or(|mode| mode == "websocket")
+                    {
+                        let obfs = if opts.contains_key("tls") {
+                            "wss"
+                        } else {
+                            "ws"
+                        };
+                        fields.push(format!("obfs={}", obfs));
+                        if let Some(host) = option("host") {
+                            fields.push(format!("obfs-host={}", host));
+                        }
+                        if let Some(path) = option("path") {
+                            fields.push(format!("obfs-uri={}", path));
+                        }
+                    } else {
+                        return Err(unsupported(&format!("the {} plugin", plugin)));
+                    }
+                }
+                fields.push("udp-relay=true".to_string());
+            }
+            Self::ShadowsocksR {
+                method,
+                password,
+                host,
+                port,
+                ssr_protocol,
+                protocol_param,
+                obfs,
+                obfs_param,
+                ..
+            } => {
+                fields.extend([
+                    format!("shadowsocks={}", server(host, *port)),
+                    format!("method={}", method),
+                    format!("password={}", password),
+                    format!("ssr-protocol={}", ssr_protocol),
+                ]);
+                if let Some(protocol_param) = protocol_param {
+                    fields.push(format!("ssr-protocol-param={}", protocol_param));
+                }
+                fields.push(format!("obfs={}", obfs));
+                if let Some(obfs_param) = obfs_param {
+                    fields.push(format!("obfs-host={}", obfs_param));
+                }
+            }
+            Self::Vmess {
+                uuid,
+                host,
+                port,
+                alter_id,
+                security,
+                transport,
+                tls,
+                ..
+            } => {
+                // Quantumult X has no `auto`; it picks ChaCha20 on devices without AES.
+                let method = match security.as_str() {
+                    "aes-128-gcm" => "aes-128-gcm",
+                    "none" | "zero" => "none",
+                    _ => "chacha20-poly1305",
+                };
+                fields.extend([
+                    format!("vmess={}", server(host, *port)),
+                    format!("method={}", method),
+                    format!("password={}", uuid),
+                ]);
+                fields.extend(obfs_fields(transport, tls)?);
+                fields.push(format!("aead={}", parse_alter_id(alter_id)? == 0));
+            }
+            Self::Vless {
+                uuid,
+                host,
+                port,
+                flow,
+                transport,
+                tls,
+                ..
+            } => {
+                fields.extend([
+                    format!("vless={}", server(host, *port)),
+                    "method=none".to_string(),
+                    format!("password={}", uuid),
+                ]);
+                fields.extend(obfs_fields(transport, tls)?);
+                if let Some(reality) = &tls.reality {
+                    fields.push(format!("reality-base64-pubkey={}", reality.public_key));
+                    if !reality.short_id.is_empty() {
+                        fields.push(format!("reality-hex-shortid={}", reality.short_id));
+                    }
+                }
+                if let Some(flow) = flow {
+                    fields.push(format!("vless-flow={}", flow));
+                }
+            }
+            Self::Trojan {
+                password,
+                host,
+                port,
+                transport,
+                tls,
+                ..
+            } => {
+                fields.extend([
+                    format!("trojan={}", server(host, *port)),
+                    format!("password={}", password),
+                ]);
+                match transport {
+                    // Plain Trojan uses `over-tls`; the obfs form only covers websocket.
+                    TransportConfig::Tcp => {
+                        fields.push("over-tls=true".to_string());
+                        if let Some(sni) = &tls.sni {
+                            fields.push(format!("tls-host={}", sni));
+                        }
+                        if tls.insecure {
+                            fields.push("tls-verification=false".to_string());
+                        }
+                    }
+                    _ => fields.extend(obfs_fields(transport, tls)?),
+                }
+            }
+            Self::Wireguard { .. } => return Err(unsupported("WireGuard")),
+            Self::Hysteria2 { .. } => return Err(unsupported("Hysteria2")),
+            Self::Tuic { .. } => return Err(unsupported("TUIC")),
+        }
+
+        fields.push(format!("tag={}", name));
+        Ok(fields.join(", "))
+    }
+}
+
+// Quantumult X folds transport and TLS into one `obfs` mode: over-tls, ws, wss
+// or http.
+fn obfs_fields(
+    transport: &TransportConfig,
+    tls: &TlsConfig,
+) -> Result<Vec<String>, ConversionError> {
+    let (obfs, host, path) = match (transport, tls.enabled) {
+        (TransportConfig::Tcp, false) => return Ok(Vec::new()),
+        (TransportConfig::Tcp, true) => ("over-tls", tls.sni.as_ref(), None),
+        (TransportConfig::Websocket { path, headers, .. }, enabled) => (
+            if enabled { "wss" } else { "ws" },
+            headers.get("Host").or(tls.sni.as_ref()),
+            Some(path),
+        ),
+        (TransportConfig::Http { host, path, .. }, false) => ("http", host.first(), Some(path)),
+        _ => return Err(unsupported("transports other than tcp, ws and http")),
+    };
+
+    let mut fields = vec![format!("obfs={}", obfs)];
+    if let Some(host) = host {
+        fields.push(format!("obfs-host={}", host));
+    }
+    if let Some(path) = path.filter(|p| !p.is_empty()) {
+        fields.push(format!("obfs-uri={}", path));
+    }
+    if tls.insecure {
+        fields.push("tls-verification=false".to_string());
+    }
+    Ok(fields)
+}
+
+fn server(host: &str, port: u16) -> String {
+    if host.contains(':') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
+
+fn unsupported(feature: &str) -> ConversionError {
+    ConversionError::UnsupportedFeature(format!("Quantumult X does not support {}", feature))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::tls::RealityConfig;
+    use std::collections::HashMap;
+
+    fn vmess(alter_id: &str) -> Protocol {
+        Protocol::Vmess {
+            uuid: "uuid".to_string(),
+            host: "example.com".to_string(),
+            port: 443,
+            alter_id: alter_id.to_string(),
+            security: "auto".to_string(),
+            packet_encoding: None,
+            transport: TransportConfig::Websocket {
+                path: "/ws".to_string(),
+                headers: HashMap::from([("Host".to_string(), "cdn.example.com".to_string())]),
+                max_early_data: 0,
+                early_data_header_name: String::new(),
+            },
+            tls: TlsConfig {
+                enabled: true,
+                sni: Some("sni.example.com".to_string()),
+                ..Default::default()
+            },
+            remark: Some("V".to_string()),
+        }
+    }
+
+    #[test]
+    fn shadowsocks_line_maps_v2ray_plugin_to_obfs() {
+        let ss = Protocol::Shadowsocks {
+            method: "aes-256-gcm".to_string(),
+            password: "secret".to_string(),
+            host: "2001:db8::1".to_string(),
+            port: 8388,
+            plugin: Some("v2ray-plugin".to_string()),
+            plugin_opts: Some("tls;host=cdn.example.com;path=/ws".to_string()),
+            remark: Some("S,1".to_string()),
+        };
+        assert_eq!(
+            ss.to_quantumult_proxy().unwrap(),
+            "shadowsocks=[2001:db8::1]:8388, method=aes-256-gcm, password=secret, obfs=wss, \
+             obfs-host=cdn.example.com, obfs-uri=/ws, udp-relay=true, tag=S 1"
+        );
+    }
+
+    #[test]
+    fn vmess_line_folds_ws_and_tls_into_obfs() {
+        assert_eq!(
+            vmess("0").to_quantumult_proxy().unwrap(),
+            "vmess=example.com:443, method=chacha20-poly1305, password=uuid, obfs=wss, \
+             obfs-host=cdn.example.com, obfs-uri=/ws, aead=true, tag=V"
+        );
+        assert!(vmess("64")
+            .to_quantumult_proxy()
+            .unwrap()
+            .contains("aead=false"));
+        assert!(matches!(
+            vmess("x").to_quantumult_proxy(),
+            Err(ConversionError::ParseError(_))
+        ));
+    }
+
+    #[test]
+    fn trojan_and_vless_lines_use_over_tls() {
+        let tls = TlsConfig {
+            enabled: true,
+            insecure: true,
+            sni: Some("sni.example.com".to_string()),
+            ..Default::default()
+        };
+        let trojan = Protocol::Trojan {
+            password: "secret".to_string(),
+            host: "example.com".to_string(),
+            port: 443,
+            transport: TransportConfig::Tcp,
+            tls: tls.clone(),
+            remark: Some("T".to_string()),
+        };
+        assert_eq!(
+            trojan.to_quantumult_proxy().unwrap(),
+            "trojan=example.com:443, password=secret, over-tls=true, tls-host=sni.example.com, \
+             tls-verification=false, tag=T"
+        );
+
+        let vless = Protocol::Vless {
+            uuid: "uuid".to_string(),
+            host: "example.com".to_string(),
+            port: 443,
+            flow: Some("xtls-rprx-vision".to_string()),
+            packet_encoding: None,
+            transport: TransportConfig::Tcp,
+            tls: TlsConfig {
+                insecure: false,
+                reality: Some(RealityConfig {
+                    public_key: "pbk".to_string(),
+                    short_id: "ab12".to_string(),
+                }),
+                ..tls
+            },
+            remark: Some("R".to_string()),
+        };
+        assert_eq!(
+            vless.to_quantumult_proxy().unwrap(),
+            "vless=example.com:443, method=none, password=uuid, obfs=over-tls, \
+             obfs-host=sni.example.com, reality-base64-pubkey=pbk, reality-hex-shortid=ab12, \
+             vless-flow=xtls-rprx-vision, tag=R"
+        );
+    }
+}
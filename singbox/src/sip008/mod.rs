@@ -0,0 +1,168 @@
+use crate::error::ConversionError;
+use crate::protocol::Protocol;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use uuid::Uuid;
+
+// The SIP008 online config document published by Outline and other
+// Shadowsocks providers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sip008Config {
+    pub version: u32,
+    pub servers: Vec<Sip008Server>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes_used: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes_remaining: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sip008Server {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remarks: Option<String>,
+    pub server: String,
+    pub server_port: u16,
+    pub password: String,
+    pub method: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plugin: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plugin_opts: Option<String>,
+}
+
+impl Sip008Config {
+    // Builds a version 1 document from the Shadowsocks nodes in `protocols`;
+    // other protocols are skipped. Server ids are derived from the server
+    // details, so the same node keeps its id across exports.
+    pub fn from_protocols(protocols: &[Protocol]) -> Self {
+        let mut servers = Vec::new();
+        for protocol in protocols {
+            let Protocol::Shadowsocks {
+                method,
+                password,
+                host,
+                port,
+                plugin,
+                plugin_opts,
+                remark,
+            } = protocol
+            else {
+                log::warn!(
+                    "Skipping {} in SIP008 config: not a Shadowsocks node",
+                    protocol.display_name()
+                );
+                continue;
+            };
+            servers.push(Sip008Server {
+                id: server_id(&[
+                    host,
+                    &port.to_string(),
+                    method,
+                    password,
+                    plugin.as_deref().unwrap_or_default(),
+                    plugin_opts.as_deref().unwrap_or_default(),
+                ]),
+                remarks: remark.clone(),
+                server: host.clone(),
+                server_port: *port,
+                password: password.clone(),
+                method: method.clone(),
+                plugin: plugin.clone(),
+                plugin_opts: plugin_opts.clone(),
+            });
+        }
+
+        Self {
+            version: 1,
+            servers,
+            bytes_used: None,
+            bytes_remaining: None,
+        }
+    }
+
+    pub fn to_protocols(&self) -> Vec<Protocol> {
+        self.servers
+            .iter()
+            .map(|server| Protocol::Shadowsocks {
+                method: server.method.clone(),
+                password: server.password.clone(),
+                host: server.server.clone(),
+                port: server.server_port,
+                plugin: server.plugin.clone().filter(|p| !p.is_empty()),
+                plugin_opts: server.plugin_opts.clone().filter(|o| !o.is_empty()),
+                remark: server.remarks.clone().filter(|r| !r.is_empty()),
+            })
+            .collect()
+    }
+
+    pub fn to_json(&self) -> Result<String, ConversionError> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| ConversionError::SerializationError(e.to_string()))
+    }
+
+    pub fn save_to_file(&self, filename: &str) -> Result<(), ConversionError> {
+        fs::write(Path::new(filename), self.to_json()?)
+            .map_err(|e| ConversionError::IoError(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+// Reads a SIP008 document and returns its servers as Shadowsocks nodes.
+pub fn parse_config(content: &str) -> Result<Vec<Protocol>, ConversionError> {
+    let config: Sip008Config =
+        serde_json::from_str(content).map_err(|e| ConversionError::ParseError(e.to_string()))?;
+    if config.version != 1 {
+        return Err(ConversionError::InvalidVersion(format!(
+            "unsupported SIP008 version {}",
+            config.version
+        )));
+    }
+    Ok(config.to_protocols())
+}
+
+// SIP008 wants a UUID per server. This is a name-based (version 5) UUID over
+// every field of the server except its remark, so the id is stable across
+// exports and builds and changes whenever the connection details do.
+fn server_id(server: &[&str]) -> String {
+    let name = serde_json::to_string(server).unwrap_or_default();
+    Uuid::new_v5(&Uuid::NAMESPACE_OID, name.as_bytes()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shadowsocks(plugin_opts: Option<&str>) -> Protocol {
+        Protocol::Shadowsocks {
+            method: "aes-256-gcm".to_string(),
+            password: "secret".to_string(),
+            host: "example.com".to_string(),
+            port: 8388,
+            plugin: plugin_opts.map(|_| "obfs-local".to_string()),
+            plugin_opts: plugin_opts.map(String::from),
+            remark: Some("Node".to_string()),
+        }
+    }
+
+    fn id(protocol: Protocol) -> String {
+        Sip008Config::from_protocols(&[protocol]).servers[0]
+            .id
+            .clone()
+    }
+
+    #[test]
+    fn server_ids_are_stable_and_cover_the_plugin() {
+        assert_eq!(
+            id(shadowsocks(None)),
+            "e808f06e-8332-51fb-a1b3-191dd6b4f486"
+        );
+        assert_ne!(
+            id(shadowsocks(Some("obfs=http"))),
+            id(shadowsocks(Some("obfs=tls")))
+        );
+        assert_ne!(id(shadowsocks(None)), id(shadowsocks(Some("obfs=http"))));
+    }
+}
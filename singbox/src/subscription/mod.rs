@@ -0,0 +1,268 @@
+use crate::error::ConversionError;
+use crate::protocol::{with_unique_names, Protocol};
+use base64::engine::general_purpose;
+use base64::Engine;
+use std::fs;
+use std::path::Path;
+
+// Traffic and expiry figures for the `subscription-userinfo` response header
+// that v2rayN, Shadowrocket and Clash clients show next to a subscription.
+// Traffic is in bytes, `expire` in Unix seconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SubscriptionUserinfo {
+    pub upload: u64,
+    pub download: u64,
+    pub total: u64,
+    pub expire: Option<i64>,
+}
+
+impl SubscriptionUserinfo {
+    pub fn to_header_value(&self) -> String {
+        let mut value = format!(
+            "upload={}; download={}; total={}",
+            self.upload, self.download, self.total
+        );
+        if let Some(expire) = self.expire {
+            value.push_str(&format!("; expire={}", expire));
+        }
+        value
+    }
+}
+
+// A link subscription as read by v2rayN, v2rayNG and Shadowrocket: one share
+// link per line, the whole body base64-encoded.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Subscription {
+    protocols: Vec<Protocol>,
+    remark_template: Option<String>,
+    userinfo: Option<SubscriptionUserinfo>,
+}
+
+impl Subscription {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn protocols(&self) -> &[Protocol] {
+        &self.protocols
+    }
+
+    pub fn userinfo(&self) -> Option<&SubscriptionUserinfo> {
+        self.userinfo.as_ref()
+    }
+
+    pub fn with_protocols(mut self, protocols: &[Protocol]) -> Self {
+        self.protocols.extend_from_slice(protocols);
+        self
+    }
+
+    // Rewrites every `#remark`. The template may use `{name}` (the current
+    // remark, or `host:port`), `{index}` (from 1), `{protocol}` and `{host}`.
+    pub fn with_remarks(mut self, template: &str) -> Self {
+        self.remark_template = Some(template.to_string());
+        self
+    }
+
+    pub fn with_userinfo(mut self, userinfo: SubscriptionUserinfo) -> Self {
+        self.userinfo = Some(userinfo);
+        self
+    }
+
+    // The share links, in order. Rewritten remarks are made unique, since
+    // clients list nodes by name.
+    pub fn links(&self) -> Vec<String> {
+        let Some(template) = &self.remark_template else {
+            return self.protocols.iter().map(Protocol::to_uri).collect();
+        };
+
+        let renamed = self
+            .protocols
+            .iter()
+            .enumerate()
+            .map(|(index, protocol)| {
+                let remark = fill_template(
+                    template,
+                    &[
+                        ("{name}", protocol.display_name()),
+                        ("{index}", (index + 1).to_string()),
+                        ("{protocol}", protocol_name(protocol).to_string()),
+                        ("{host}", protocol_host(protocol).to_string()),
+                    ],
+                );
+                let mut protocol = protocol.clone();
+                protocol.set_remark(Some(remark));
+                protocol
+            })
+            .collect::<Vec<_>>();
+        with_unique_names(&renamed)
+            .iter()
+            .map(Protocol::to_uri)
+            .collect()
+    }
+
+    // The response body: newline-joined links in standard, padded base64.
+    pub fn to_base64(&self) -> String {
+        general_purpose::STANDARD.encode(self.links().join("\n"))
+    }
+
+    // The `subscription-userinfo` header value to serve alongside the body.
+    pub fn userinfo_header(&self) -> Option<String> {
+        self.userinfo
+            .as_ref()
+            .map(SubscriptionUserinfo::to_header_value)
+    }
+
+    pub fn save_to_file(&self, filename: &str) -> Result<(), ConversionError> {
+        fs::write(Path::new(filename), self.to_base64())
+            .map_err(|e| ConversionError::IoError(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+// Substitutes the placeholders in one pass over the template, so a value that
+// itself contains `{index}` or `{host}` is copied as is.
+fn fill_template(template: &str, values: &[(&str, String)]) -> String {
+    let mut filled = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        filled.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match values
+            .iter()
+            .find(|(placeholder, _)| rest.starts_with(placeholder))
+        {
+            Some((placeholder, value)) => {
+                filled.push_str(value);
+                rest = &rest[placeholder.len()..];
+            }
+            None => {
+                filled.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    filled.push_str(rest);
+    filled
+}
+
+fn protocol_name(protocol: &Protocol) -> &'static str {
+    match protocol {
+        Protocol::Shadowsocks { .. } => "ss",
+        Protocol::ShadowsocksR { .. } => "ssr",
+        Protocol::Vmess { .. } => "vmess",
+        Protocol::Vless { .. } => "vless",
+        Protocol::Trojan { .. } => "trojan",
+        Protocol::Hysteria2 { .. } => "hysteria2",
+        Protocol::Tuic { .. } => "tuic",
+        Protocol::Wireguard { .. } => "wireguard",
+    }
+}
+
+fn protocol_host(protocol: &Protocol) -> &str {
+    match protocol {
+        Protocol::Shadowsocks { host, .. }
+        | Protocol::ShadowsocksR { host, .. }
+        | Protocol::Vmess { host, .. }
+        | Protocol::Vless { host, .. }
+        | Protocol::Trojan { host, .. }
+        | Protocol::Hysteria2 { host, .. }
+        | Protocol::Tuic { host, .. } => host,
+        // `host:port` or `[v6]:port`
+        Protocol::Wireguard { endpoint, .. } => endpoint
+            .rsplit_once(':')
+            .map_or(endpoint.as_str(), |(host, _)| host)
+            .trim_start_matches('[')
+            .trim_end_matches(']'),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn links() -> Vec<Protocol> {
+        [
+            "trojan://secret@example.com:443?security=tls&type=tcp#%7Bindex%7D%20%7Bhost%7D",
+            "ss://YWVzLTI1Ni1nY206c2VjcmV0@198.51.100.1:8388#Node",
+            "wireguard://priv@[2001:db8::1]:51820?publickey=pub&ip=10.0.0.2/32",
+        ]
+        .iter()
+        .map(|uri| Protocol::parse_uri(uri).unwrap())
+        .collect()
+    }
+
+    fn remarks(subscription: &Subscription) -> Vec<String> {
+        subscription
+            .links()
+            .iter()
+            .map(|link| Protocol::parse_uri(link).unwrap().display_name())
+            .collect()
+    }
+
+    #[test]
+    fn body_is_base64_of_the_links() {
+        let subscription = Subscription::new().with_protocols(&links());
+        let body = general_purpose::STANDARD
+            .decode(subscription.to_base64())
+            .unwrap();
+        let body = String::from_utf8(body).unwrap();
+        assert_eq!(body.lines().count(), 3);
+        assert_eq!(body, subscription.links().join("\n"));
+        let parsed: Vec<_> = body
+            .lines()
+            .map(|link| Protocol::parse_uri(link).unwrap())
+            .collect();
+        assert_eq!(parsed, links());
+    }
+
+    #[test]
+    fn remarks_are_rewritten_in_one_pass() {
+        let subscription = Subscription::new()
+            .with_protocols(&links())
+            .with_remarks("{index}. {protocol} {host} ({name}) {other}");
+        assert_eq!(
+            remarks(&subscription),
+            vec![
+                "1. trojan example.com ({index} {host}) {other}",
+                "2. ss 198.51.100.1 (Node) {other}",
+                "3. wireguard 2001:db8::1 ([2001:db8::1]:51820) {other}",
+            ]
+        );
+    }
+
+    #[test]
+    fn rewritten_remarks_stay_unique() {
+        let subscription = Subscription::new()
+            .with_protocols(&links())
+            .with_remarks("Server");
+        assert_eq!(
+            remarks(&subscription),
+            vec!["Server", "Server 2", "Server 3"]
+        );
+    }
+
+    #[test]
+    fn userinfo_header_lists_traffic_and_expiry() {
+        assert_eq!(Subscription::new().userinfo_header(), None);
+        let userinfo = SubscriptionUserinfo {
+            upload: 1,
+            download: 2,
+            total: 1073741824,
+            expire: None,
+        };
+        let subscription = Subscription::new().with_userinfo(userinfo);
+        assert_eq!(
+            subscription.userinfo_header().as_deref(),
+            Some("upload=1; download=2; total=1073741824")
+        );
+        let subscription = subscription.with_userinfo(SubscriptionUserinfo {
+            expire: Some(1767225600),
+            ..userinfo
+        });
+        assert_eq!(
+            subscription.userinfo_header().as_deref(),
+            Some("upload=1; download=2; total=1073741824; expire=1767225600")
+        );
+    }
+}
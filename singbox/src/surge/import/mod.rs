@@ -0,0 +1,347 @@
+use crate::error::ConversionError;
+use crate::profile::{profile_sections, split_fields, split_options, unquote};
+use crate::protocol::tls::TlsConfig;
+use crate::protocol::transport::TransportConfig;
+use crate::protocol::Protocol;
+use std::collections::HashMap;
+
+// Reads the `[Proxy]` section of a Surge profile. WireGuard proxies are
+// resolved against their `[WireGuard name]` sections; proxy types we do not
+// support and lines we cannot read are skipped.
+pub fn parse_config(content: &str) -> Result<Vec<Protocol>, ConversionError> {
+    let sections = profile_sections(content);
+    let wireguard = sections
+        .iter()
+        .filter_map(|(name, lines)| Some((name.strip_prefix("WireGuard ")?.trim(), lines)))
+        .map(|(name, lines)| (name.to_string(), section_options(lines)))
+        .collect::<HashMap<_, _>>();
+    let proxies = sections
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("Proxy"))
+        .ok_or(ConversionError::MissingField("[Proxy]"))?;
+
+    let mut protocols = Vec::new();
+    for line in &proxies.1 {
+        match parse_line(line, &wireguard) {
+            Ok(protocol) => protocols.push(protocol),
+            Err(ConversionError::UnsupportedProtocol(proxy_type)) => {
+                log::warn!("Skipping unsupported Surge proxy type: {}", proxy_type);
+            }
+            Err(e) => log::warn!("Skipping Surge proxy line {}: {}", line, e),
+        }
+    }
+    Ok(protocols)
+}
+
+// Parses one `name = type, host, port, key=value, ...` line. WireGuard lines
+// need their section and can only be read through `parse_config`.
+pub fn parse_proxy(line: &str) -> Result<Protocol, ConversionError> {
+    parse_line(line, &HashMap::new())
+}
+
+fn parse_line(
+    line: &str,
+    wireguard: &HashMap<String, HashMap<String, String>>,
+) -> Result<Protocol, ConversionError> {
+    let (name, definition) = line
+        .split_once('=')
+        .ok_or_else(|| ConversionError::ParseError(format!("invalid proxy line: {}", line)))?;
+    let remark = Some(name.trim().to_string()).filter(|name| !name.is_empty());
+    let fields = split_fields(definition, ',');
+    let (positional, options) = split_options(&fields);
+    let option = |key: &str| options.get(key).filter(|v| !v.is_empty()).cloned();
+    let enabled = |key: &str| options.get(key).is_some_and(|v| v == "true");
+    let password = || {
+        options
+            .get("password")
+            .cloned()
+            .ok_or(ConversionError::MissingPassword)
+    };
+
+    let proxy_type = positional.first().cloned().unwrap_or_default();
+    if proxy_type == "wireguard" {
+        let section_name =
+            option("section-name").ok_or(ConversionError::MissingField("section-name"))?;
+        let section = wireguard
+            .get(&section_name)
+            .ok_or(ConversionError::MissingField("[WireGuard] section"))?;
+        return parse_wireguard(section, remark);
+    }
+    // Built-in policies such as `direct` and `reject` have no server.
+    if !matches!(
+        proxy_type.as_str(),
+        "ss" | "vmess" | "trojan" | "hysteria2" | "tuic-v5"
+    ) {
+        return Err(ConversionError::UnsupportedProtocol(proxy_type));
+    }
+
+    let host = positional
+        .get(1)
+        .cloned()
+        .ok_or(ConversionError::MissingHost)?;
+    let port = positional
+        .get(2)
+        .and_then(|port| port.parse().ok())
+        .ok_or(ConversionError::MissingPort)?;
+    let tls = |tls: bool| TlsConfig {
+        enabled: tls,
+        insecure: enabled("skip-cert-verify"),
+        sni: option("sni"),
+        alpn: option("alpn").into_iter().collect(),
+        ..Default::default()
+    };
+
+    match proxy_type.as_str() {
+        "ss" => {
+            let plugin_opts = option("obfs").map(|obfs| match option("obfs-host") {
+                Some(host) => format!("obfs={};obfs-host={}", obfs, host),
+                None => format!("obfs={}", obfs),
+            });
+            Ok(Protocol::Shadowsocks {
+                method: option("encrypt-method")
+                    .ok_or(ConversionError::MissingField("encrypt-method"))?,
+                password: password()?,
+                host,
+                port,
+                plugin: plugin_opts.is_some().then(|| "obfs-local".to_string()),
+                plugin_opts,
+                remark,
+            })
+        }
+        "vmess" => Ok(Protocol::Vmess {
+            uuid: option("username").ok_or(ConversionError::MissingUUID)?,
+            host,
+            port,
+            alter_id: "0".to_string(),
+            security: option("encrypt-method").unwrap_or_else(|| "auto".to_string()),
+            packet_encoding: None,
+            transport: parse_transport(&options),
+            tls: tls(enabled("tls")),
+            remark,
+        }),
+        "trojan" => Ok(Protocol::Trojan {
+            password: password()?,
+            host,
+            port,
+            transport: parse_transport(&options),
+            tls: tls(true),
+            remark,
+        }),
+        "hysteria2" => Ok(Protocol::Hysteria2 {
+            password: password()?,
+            host,
+            port,
+            obfs: None,
+            obfs_password: None,
+            up_mbps: None,
+            down_mbps: option("download-bandwidth").and_then(|down| down.parse().ok()),
+            tls: tls(true),
+            remark,
+        }),
+        "tuic-v5" => Ok(Protocol::Tuic {
+            uuid: option("uuid").ok_or(ConversionError::MissingUUID)?,
+            password: password()?,
+            host,
+            port,
+            congestion_control: None,
+            udp_relay_mode: None,
+            tls: tls(true),
+            remark,
+        }),
+        proxy_type => Err(ConversionError::UnsupportedProtocol(proxy_type.to_string())),
+    }
+}
+
+fn parse_transport(options: &HashMap<String, String>) -> TransportConfig {
+    if options.get("ws").is_none_or(|ws| ws != "true") {
+        return TransportConfig::Tcp;
+    }
+    // `ws-headers=Host:"example.com"|User-Agent:"..."`
+    let headers = options
+        .get("ws-headers")
+        .map(|headers| {
+            split_fields(headers, '|')
+                .into_iter()
+                .filter_map(|header| header.split_once(':'))
+                .map(|(k, v)| (k.trim().to_string(), unquote(v.trim()).to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+    TransportConfig::Websocket {
+        path: options.get("ws-path").cloned().unwrap_or_default(),
+        headers,
+        max_early_data: 0,
+        early_data_header_name: String::new(),
+    }
+}
+
+fn parse_wireguard(
+    section: &HashMap<String, String>,
+    remark: Option<String>,
+) -> Result<Protocol, ConversionError> {
+    // `peer = (public-key = ..., allowed-ips = "...", endpoint = host:port)`
+    let peer = section
+        .get("peer")
+        .map(|peer| {
+            let peer = peer.trim().trim_start_matches('(').trim_end_matches(')');
+            section_options(&split_fields(peer, ','))
+        })
+        .ok_or(ConversionError::MissingField("peer"))?;
+
+    let mut ip = Vec::new();
+    if let Some(ipv4) = section.get("self-ip") {
+        ip.push(if ipv4.contains('/') {
+            ipv4.clone()
+        } else {
+            format!("{}/32", ipv4)
+        });
+    }
+    if let Some(ipv6) = section.get("self-ip-v6") {
+        ip.push(if ipv6.contains('/') {
+            ipv6.clone()
+        } else {
+            format!("{}/128", ipv6)
+        });
+    }
+    if ip.is_empty() {
+        return Err(ConversionError::MissingIP);
+    }
+
+    Ok(Protocol::Wireguard {
+        private_key: section
+            .get("private-key")
+            .cloned()
+            .ok_or(ConversionError::MissingField("private-key"))?,
+        public_key: peer
+            .get("public-key")
+            .cloned()
+            .ok_or(ConversionError::MissingPublicKey)?,
+        endpoint: peer
+            .get("endpoint")
+            .cloned()
+            .ok_or(ConversionError::MissingHost)?,
+        dns: section
+            .get("dns-server")
+            .map(|dns| split_fields(dns, ',').join(",")),
+        mtu: section.get("mtu").and_then(|mtu| mtu.parse().ok()),
+        ip: ip.join(","),
+        pre_shared_key: peer.get("preshared-key").cloned(),
+        persistent_keepalive: peer
+            .get("keepalive")
+            .and_then(|keepalive| keepalive.parse().ok()),
+        amnezia: None,
+        remark,
+    })
+}
+
+// `key = value` lines of a `[WireGuard name]` section, or the pairs inside its
+// `peer = (...)` value.
+fn section_options(lines: &[&str]) -> HashMap<String, String> {
+    lines
+        .iter()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_lowercase(), unquote(value.trim()).to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROFILE: &str = r#"
+[General]
+loglevel = notify
+
+[Proxy]
+Direct = direct
+SS = ss, example.com, 8388, encrypt-method=aes-256-gcm, password="pa,ss", obfs=tls, obfs-host=cdn.example.com
+VMess = vmess, example.com, 443, username=uuid, ws=true, ws-path=/ws, ws-headers=Host:"cdn.example.com"|User-Agent:"a|b", tls=true, sni=sni.example.com
+Trojan = trojan, example.com, 443, password=secret, sni=sni.example.com, skip-cert-verify=true
+Broken = trojan, example.com, 443
+WG = wireguard, section-name=Home
+
+[WireGuard Home]
+private-key = priv
+self-ip = 10.0.0.2
+self-ip-v6 = fd00::2
+dns-server = 1.1.1.1, 8.8.8.8
+mtu = 1280
+peer = (public-key = pub, allowed-ips = "0.0.0.0/0, ::/0", endpoint = example.com:51820, keepalive = 25)
+"#;
+
+    #[test]
+    fn proxy_section_is_read() {
+        let protocols = parse_config(PROFILE).unwrap();
+        let names: Vec<_> = protocols.iter().map(|p| p.remark().unwrap()).collect();
+        assert_eq!(names, vec!["SS", "VMess", "Trojan", "WG"]);
+
+        let Protocol::Shadowsocks {
+            password,
+            plugin,
+            plugin_opts,
+            ..
+        } = &protocols[0]
+        else {
+            panic!("expected Shadowsocks");
+        };
+        assert_eq!(password, "pa,ss");
+        assert_eq!(plugin.as_deref(), Some("obfs-local"));
+        assert_eq!(
+            plugin_opts.as_deref(),
+            Some("obfs=tls;obfs-host=cdn.example.com")
+        );
+
+        let Protocol::Vmess { transport, tls, .. } = &protocols[1] else {
+            panic!("expected VMess");
+        };
+        assert_eq!(
+            transport,
+            &TransportConfig::Websocket {
+                path: "/ws".to_string(),
+                headers: HashMap::from([
+                    ("Host".to_string(), "cdn.example.com".to_string()),
+                    ("User-Agent".to_string(), "a|b".to_string()),
+                ]),
+                max_early_data: 0,
+                early_data_header_name: String::new(),
+            }
+        );
+        assert!(tls.enabled);
+        assert_eq!(tls.sni.as_deref(), Some("sni.example.com"));
+
+        let Protocol::Trojan { tls, .. } = &protocols[2] else {
+            panic!("expected Trojan");
+        };
+        assert!(tls.enabled && tls.insecure);
+    }
+
+    #[test]
+    fn wireguard_is_read_from_its_section() {
+        let protocols = parse_config(PROFILE).unwrap();
+        assert_eq!(
+            protocols[3],
+            Protocol::Wireguard {
+                private_key: "priv".to_string(),
+                public_key: "pub".to_string(),
+                endpoint: "example.com:51820".to_string(),
+                dns: Some("1.1.1.1,8.8.8.8".to_string()),
+                mtu: Some(1280),
+                ip: "10.0.0.2/32,fd00::2/128".to_string(),
+                pre_shared_key: None,
+                persistent_keepalive: Some(25),
+                amnezia: None,
+                remark: Some("WG".to_string()),
+            }
+        );
+        assert!(parse_proxy("WG = wireguard, section-name=Home").is_err());
+    }
+
+    #[test]
+    fn exported_lines_read_back() {
+        let protocols = parse_config(PROFILE).unwrap();
+        for protocol in &protocols[..3] {
+            let line = protocol.to_surge_proxy().unwrap();
+            assert_eq!(&parse_proxy(&line).unwrap(), protocol, "{}", line);
+        }
+    }
+}
@@ -0,0 +1,374 @@
+use crate::error::ConversionError;
+use crate::protocol::tls::TlsConfig;
+use crate::protocol::transport::TransportConfig;
+use crate::protocol::{parse_alter_id, plugin_options, split_addresses, Protocol, SIMPLE_OBFS};
+
+impl Protocol {
+    // A Surge `[Proxy]` line: `name = type, host, port, key=value, ...`.
+    // WireGuard lines only point at a `[WireGuard name]` section, rendered by
+    // `to_surge_wireguard_section`.
+    pub fn to_surge_proxy(&self) -> Result<String, ConversionError> {
+        let name = self.display_name().replace([',', '='], " ");
+        let mut fields = Vec::new();
+
+        match self {
+            Self::Shadowsocks {
+                method,
+                password,
+                host,
+                port,
+                plugin,
+                plugin_opts,
+                ..
+            } => {
+                fields.extend([
+                    "ss".to_string(),
+                    host.clone(),
+                    port.to_string(),
+                    format!("encrypt-method={}", method),
+                    format!("password={}", quoted(password)),
+                ]);
+                if let Some(plugin) = plugin {
+                    if !SIMPLE_OBFS.contains(&plugin.as_str()) {
+                        return Err(unsupported(&format!("the {} plugin", plugin)));
+                    }
+                    let opts = plugin_options(plugin_opts.as_deref());
+                    fields.push(format!(
+                        "obfs={}",
+                        opts.get("obfs").map_or("http", String::as_str)
+                    ));
+                    if let Some(host) = opts.get("obfs-host") {
+                        fields.push(format!("obfs-host={}", host));
+                    }
+                }
+                fields.push("udp-relay=true".to_string());
+            }
+            Self::Vmess {
+                uuid,
+                host,
+                port,
+                alter_id,
+                transport,
+                tls,
+                ..
+            } => {
+                fields.extend([
+                    "vmess".to_string(),
+                    host.clone(),
+                    port.to_string(),
+                    format!("username={}", uuid),
+                ]);
+                fields.extend(transport_fields(transport)?);
+                if tls.enabled {
+                    fields.push("tls=true".to_string());
+                    fields.extend(tls_fields(tls)?);
+                }
+                if parse_alter_id(alter_id)? == 0 {
+                    fields.push("vmess-aead=true".to_string());
+                }
+            }
+            Self::Trojan {
+                password,
+                host,
+                port,
+                transport,
+                tls,
+                ..
+            } => {
+                fields.extend([
+                    "trojan".to_string(),
+                    host.clone(),
+                    port.to_string(),
+                    format!("password={}", quoted(password)),
+                ]);
+                fields.extend(transport_fields(transport)?);
+                fields.extend(tls_fields(tls)?);
+            }
+            Self::Hysteria2 {
+                password,
+                host,
+                port,
+                obfs,
+                down_mbps,
+                tls,
+                ..
+            } => {
+                if obfs.is_some() {
+                    return Err(unsupported("Hysteria2 obfuscation"));
+                }
+                fields.extend([
+                    "hysteria2".to_string(),
+                    host.clone(),
+                    port.to_string(),
+                    format!("password={}", quoted(password)),
+                ]);
+                if let Some(down) = down_mbps {
+                    fields.push(format!("download-bandwidth={}", down));
+                }
+                fields.extend(tls_fields(tls)?);
+            }
+            Self::Tuic {
+                uuid,
+                password,
+                host,
+                port,
+                tls,
+                ..
+            } => {
+                fields.extend([
+                    "tuic-v5".to_string(),
+                    host.clone(),
+                    port.to_string(),
+                    format!("password={}", quoted(password)),
+                    format!("uuid={}", uuid),
+                ]);
+                if !tls.alpn.is_empty() {
+                    fields.push(format!("alpn={}", tls.alpn.join(",")));
+                }
+                fields.extend(tls_fields(tls)?);
+            }
+            Self::Wireguard { amnezia, .. } => {
+                if amnezia.is_some() {
+                    return Err(unsupported("AmneziaWG obfuscation"));
+                }
+                fields.extend(["wireguard".to_string(), format!("section-name={}", name)]);
+            }
+            Self::Vless { .. } => return Err(unsupported("VLESS")),
+            Self::ShadowsocksR { .. } => return Err(unsupported("ShadowsocksR")),
+        }
+
+        Ok(format!("{} = {}", name, fields.join(", ")))
+    }
+
+    // The `[WireGuard name]` section a Surge WireGuard proxy line refers to.
+    pub fn to_surge_wireguard_section(&self) -> Option<String> {
+        let Self::Wireguard {
+            private_key,
+            public_key,
+            endpoint,
+            dns,
+            mtu,
+            ip,
+            pre_shared_key,
+            persistent_keepalive,
+            ..
+        } = self
+        else {
+            return None;
+        };
+
+        let name = self.display_name().replace([',', '='], " ");
+        let mut lines = vec![
+            format!("[WireGuard {}]", name),
+            format!("private-key = {}", private_key),
+        ];
+        for address in split_addresses(ip) {
+            let (address, key) = if address.contains(':') {
+                (address.trim_end_matches("/128"), "self-ip-v6")
+            } else {
+                (address.trim_end_matches("/32"), "self-ip")
+            };
+            lines.push(format!("{} = {}", key, address));
+        }
+        if let Some(dns) = dns {
+            lines.push(format!("dns-server = {}", dns.replace(',', ", ")));
+        }
+        if let Some(mtu) = mtu {
+            lines.push(format!("mtu = {}", mtu));
+        }
+        let mut peer = vec![
+            format!("public-key = {}", public_key),
+            "allowed-ips = \"0.0.0.0/0, ::/0\"".to_string(),
+            format!("endpoint = {}", endpoint),
+        ];
+        if let Some(pre_shared_key) = pre_shared_key {
+            peer.push(format!("preshared-key = {}", pre_shared_key));
+        }
+        if let Some(keepalive) = persistent_keepalive {
+            peer.push(format!("keepalive = {}", keepalive));
+        }
+        lines.push(format!("peer = ({})", peer.join(", ")));
+        Some(lines.join("\n"))
+    }
+}
+
+fn transport_fields(transport: &TransportConfig) -> Result<Vec<String>, ConversionError> {
+    let mut fields = Vec::new();
+    match transport {
+        TransportConfig::Tcp => {}
+        TransportConfig::Websocket { path, headers, .. } => {
+            fields.push("ws=true".to_string());
+            if !path.is_empty() {
+                fields.push(format!("ws-path={}", path));
+            }
+            if !headers.is_empty() {
+                let mut headers = headers
+                    .iter()
+                    .map(|(k, v)| format!("{}:\"{}\"", k, v))
+                    .collect::<Vec<_>>();
+                headers.sort();
+                fields.push(format!("ws-headers={}", headers.join("|")));
+            }
+        }
+        _ => return Err(unsupported("transports other than tcp and ws")),
+    }
+    Ok(fields)
+}
+
+fn tls_fields(tls: &TlsConfig) -> Result<Vec<String>, ConversionError> {
+    if tls.reality.is_some() {
+        return Err(unsupported("Reality"));
+    }
+    let mut fields = Vec::new();
+    if let Some(sni) = &tls.sni {
+        fields.push(format!("sni={}", sni));
+    }
+    if tls.insecure {
+        fields.push("skip-cert-verify=true".to_string());
+    }
+    Ok(fields)
+}
+
+// Values containing the field separator must be quoted to stay one field.
+fn quoted(value: &str) -> String {
+    if value.contains(',') {
+        format!("\"{}\"", value)
+    } else {
+        value.to_string()
+    }
+}
+
+fn unsupported(feature: &str) -> ConversionError {
+    ConversionError::UnsupportedFeature(format!("Surge does not support {}", feature))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::tls::RealityConfig;
+    use std::collections::HashMap;
+
+    fn vmess(alter_id: &str) -> Protocol {
+        Protocol::Vmess {
+            uuid: "uuid".to_string(),
+            host: "example.com".to_string(),
+            port: 443,
+            alter_id: alter_id.to_string(),
+            security: "auto".to_string(),
+            packet_encoding: None,
+            transport: TransportConfig::Websocket {
+                path: "/ws".to_string(),
+                headers: HashMap::from([("Host".to_string(), "cdn.example.com".to_string())]),
+                max_early_data: 0,
+                early_data_header_name: String::new(),
+            },
+            tls: TlsConfig {
+                enabled: true,
+                sni: Some("sni.example.com".to_string()),
+                ..Default::default()
+            },
+            remark: Some("V".to_string()),
+        }
+    }
+
+    #[test]
+    fn shadowsocks_line_carries_simple_obfs() {
+        let ss = Protocol::Shadowsocks {
+            method: "aes-256-gcm".to_string(),
+            password: "se,cret".to_string(),
+            host: "example.com".to_string(),
+            port: 8388,
+            plugin: Some("obfs-local".to_string()),
+            plugin_opts: Some("obfs=tls;obfs-host=cdn.example.com".to_string()),
+            remark: Some("S, 1".to_string()),
+        };
+        assert_eq!(
+            ss.to_surge_proxy().unwrap(),
+            "S  1 = ss, example.com, 8388, encrypt-method=aes-256-gcm, password=\"se,cret\", \
+             obfs=tls, obfs-host=cdn.example.com, udp-relay=true"
+        );
+    }
+
+    #[test]
+    fn vmess_line_carries_ws_and_tls() {
+        assert_eq!(
+            vmess("0").to_surge_proxy().unwrap(),
+            "V = vmess, example.com, 443, username=uuid, ws=true, ws-path=/ws, \
+             ws-headers=Host:\"cdn.example.com\", tls=true, sni=sni.example.com, vmess-aead=true"
+        );
+        assert!(!vmess("64").to_surge_proxy().unwrap().contains("vmess-aead"));
+        assert!(matches!(
+            vmess("x").to_surge_proxy(),
+            Err(ConversionError::ParseError(_))
+        ));
+    }
+
+    #[test]
+    fn unsupported_nodes_are_errors() {
+        let Protocol::Vmess { tls, .. } = vmess("0") else {
+            unreachable!()
+        };
+        let reality = Protocol::Trojan {
+            password: "secret".to_string(),
+            host: "example.com".to_string(),
+            port: 443,
+            transport: TransportConfig::Tcp,
+            tls: TlsConfig {
+                reality: Some(RealityConfig {
+                    public_key: "pbk".to_string(),
+                    short_id: String::new(),
+                }),
+                ..tls
+            },
+            remark: None,
+        };
+        let vless = Protocol::Vless {
+            uuid: "uuid".to_string(),
+            host: "example.com".to_string(),
+            port: 443,
+            flow: None,
+            packet_encoding: None,
+            transport: TransportConfig::Tcp,
+            tls: TlsConfig::default(),
+            remark: None,
+        };
+        for protocol in [reality, vless] {
+            assert!(matches!(
+                protocol.to_surge_proxy(),
+                Err(ConversionError::UnsupportedFeature(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn wireguard_line_points_at_its_section() {
+        let wireguard = Protocol::Wireguard {
+            private_key: "priv".to_string(),
+            public_key: "pub".to_string(),
+            endpoint: "example.com:51820".to_string(),
+            dns: Some("1.1.1.1,8.8.8.8".to_string()),
+            mtu: Some(1280),
+            ip: "10.0.0.2/32,fd00::2/128".to_string(),
+            pre_shared_key: Some("psk".to_string()),
+            persistent_keepalive: Some(25),
+            amnezia: None,
+            remark: Some("W".to_string()),
+        };
+        assert_eq!(
+            wireguard.to_surge_proxy().unwrap(),
+            "W = wireguard, section-name=W"
+        );
+        assert_eq!(
+            wireguard.to_surge_wireguard_section().unwrap(),
+            "[WireGuard W]\n\
+             private-key = priv\n\
+             self-ip = 10.0.0.2\n\
+             self-ip-v6 = fd00::2\n\
+             dns-server = 1.1.1.1, 8.8.8.8\n\
+             mtu = 1280\n\
+             peer = (public-key = pub, allowed-ips = \"0.0.0.0/0, ::/0\", \
+             endpoint = example.com:51820, preshared-key = psk, keepalive = 25)"
+        );
+        assert_eq!(vmess("0").to_surge_wireguard_section(), None);
+    }
+}
@@ -0,0 +1,186 @@
+use crate::error::ConversionError;
+use crate::protocol::{split_addresses, Protocol};
+use std::fs;
+use std::path::Path;
+
+impl Protocol {
+    // Renders a wg-quick `.conf` file as imported by the official WireGuard
+    // apps. AmneziaWG parameters are written into `[Interface]`, which only
+    // the AmneziaWG apps understand.
+    pub fn to_wireguard_config(&self) -> Result<String, ConversionError> {
+        let Self::Wireguard {
+            private_key,
+            public_key,
+            endpoint,
+            dns,
+            mtu,
+            ip,
+            pre_shared_key,
+            persistent_keepalive,
+            amnezia,
+            remark,
+        } = self
+        else {
+            return Err(ConversionError::UnsupportedFeature(
+                "only WireGuard nodes can be exported as a .conf file".to_string(),
+            ));
+        };
+
+        let mut lines = Vec::new();
+        if let Some(remark) = remark {
+            lines.push(format!("# {}", remark));
+        }
+        lines.extend([
+            "[Interface]".to_string(),
+            format!("PrivateKey = {}", private_key),
+            format!("Address = {}", split_addresses(ip).join(", ")),
+        ]);
+        if let Some(dns) = dns {
+            lines.push(format!("DNS = {}", split_addresses(dns).join(", ")));
+        }
+        if let Some(mtu) = mtu {
+            lines.push(format!("MTU = {}", mtu));
+        }
+        if let Some(amnezia) = amnezia {
+            for (key, value) in amnezia.to_params() {
+                // `jmin` -> `Jmin`, `h1` -> `H1`
+                let key = key[..1].to_uppercase() + &key[1..];
+                lines.push(format!("{} = {}", key, value));
+            }
+        }
+
+        lines.extend([
+            String::new(),
+            "[Peer]".to_string(),
+            format!("PublicKey = {}", public_key),
+        ]);
+        if let Some(pre_shared_key) = pre_shared_key {
+            lines.push(format!("PresharedKey = {}", pre_shared_key));
+        }
+        lines.extend([
+            format!("Endpoint = {}", endpoint),
+            "AllowedIPs = 0.0.0.0/0, ::/0".to_string(),
+        ]);
+        if let Some(keepalive) = persistent_keepalive {
+            lines.push(format!("PersistentKeepalive = {}", keepalive));
+        }
+
+        Ok(lines.join("\n") + "\n")
+    }
+}
+
+pub fn save_config(protocol: &Protocol, filename: &str) -> Result<(), ConversionError> {
+    fs::write(Path::new(filename), protocol.to_wireguard_config()?)
+        .map_err(|e| ConversionError::IoError(e.to_string()))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::amnezia::AmneziaConfig;
+
+    fn wireguard() -> Protocol {
+        Protocol::Wireguard {
+            private_key: "priv".to_string(),
+            public_key: "pub".to_string(),
+            endpoint: "[2001:db8::1]:51820".to_string(),
+            dns: Some("1.1.1.1,2606:4700:4700::1111".to_string()),
+            mtu: Some(1280),
+            ip: "10.0.0.2/32,fd00::2/128".to_string(),
+            pre_shared_key: Some("psk".to_string()),
+            persistent_keepalive: Some(25),
+            amnezia: Some(AmneziaConfig {
+                jc: Some(4),
+                jmin: Some(40),
+                jmax: Some(70),
+                h1: Some(1),
+                ..Default::default()
+            }),
+            remark: Some("Home".to_string()),
+        }
+    }
+
+    #[test]
+    fn every_field_is_written() {
+        assert_eq!(
+            wireguard().to_wireguard_config().unwrap(),
+            "# Home\n\
+             [Interface]\n\
+             PrivateKey = priv\n\
+             Address = 10.0.0.2/32, fd00::2/128\n\
+             DNS = 1.1.1.1, 2606:4700:4700::1111\n\
+             MTU = 1280\n\
+             Jc = 4\n\
+             Jmin = 40\n\
+             Jmax = 70\n\
+             H1 = 1\n\
+             \n\
+             [Peer]\n\
+             PublicKey = pub\n\
+             PresharedKey = psk\n\
+             Endpoint = [2001:db8::1]:51820\n\
+             AllowedIPs = 0.0.0.0/0, ::/0\n\
+             PersistentKeepalive = 25\n"
+        );
+    }
+
+    #[test]
+    fn output_reads_back() {
+        let config = wireguard().to_wireguard_config().unwrap();
+        // The `# name` comment is only a label; a .conf file has no name field.
+        let mut expected = wireguard();
+        expected.set_remark(None);
+        assert_eq!(
+            crate::wireguard::import::parse_config(&config).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn optional_fields_are_left_out() {
+        let Protocol::Wireguard {
+            private_key,
+            public_key,
+            endpoint,
+            ..
+        } = wireguard()
+        else {
+            unreachable!()
+        };
+        let minimal = Protocol::Wireguard {
+            private_key,
+            public_key,
+            endpoint,
+            dns: None,
+            mtu: None,
+            ip: "10.0.0.2/32".to_string(),
+            pre_shared_key: None,
+            persistent_keepalive: None,
+            amnezia: None,
+            remark: None,
+        };
+        assert_eq!(
+            minimal.to_wireguard_config().unwrap(),
+            "[Interface]\n\
+             PrivateKey = priv\n\
+             Address = 10.0.0.2/32\n\
+             \n\
+             [Peer]\n\
+             PublicKey = pub\n\
+             Endpoint = [2001:db8::1]:51820\n\
+             AllowedIPs = 0.0.0.0/0, ::/0\n"
+        );
+    }
+
+    #[test]
+    fn other_protocols_are_refused() {
+        let trojan =
+            Protocol::parse_uri("trojan://secret@example.com:443?security=tls&type=tcp").unwrap();
+        assert!(matches!(
+            trojan.to_wireguard_config(),
+            Err(ConversionError::UnsupportedFeature(_))
+        ));
+    }
+}
@@ -0,0 +1,109 @@
+use crate::error::ConversionError;
+use crate::protocol::amnezia::AmneziaConfig;
+use crate::protocol::Protocol;
+use std::collections::HashMap;
+
+// Reads a wg-quick / AmneziaWG `.conf` file. `Protocol::Wireguard` holds a
+// single peer, so only the first `[Peer]` section is used.
+pub fn parse_config(content: &str) -> Result<Protocol, ConversionError> {
+    let mut interface = HashMap::new();
+    let mut peers: Vec<HashMap<String, String>> = Vec::new();
+    let mut section = None;
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if line.starts_with('[') && line.ends_with(']') {
+            let name = line[1..line.len() - 1].trim().to_lowercase();
+            if name == "peer" {
+                peers.push(HashMap::new());
+            }
+            section = Some(name);
+            continue;
+        }
+
+        // Keys are base64 and end in `=`, so split on the first one only.
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| ConversionError::ParseError(format!("invalid line: {}", line)))?;
+        let (key, value) = (key.trim().to_lowercase(), value.trim().to_string());
+        match section.as_deref() {
+            Some("interface") => append(&mut interface, key, value),
+            Some("peer") => {
+                if let Some(peer) = peers.last_mut() {
+                    append(peer, key, value);
+                }
+            }
+            _ => {
+                return Err(ConversionError::ParseError(format!(
+                    "key outside of a section: {}",
+                    key
+                )))
+            }
+        }
+    }
+
+    let peer = peers.first().ok_or(ConversionError::MissingField("Peer"))?;
+
+    Ok(Protocol::Wireguard {
+        private_key: interface
+            .get("privatekey")
+            .cloned()
+            .ok_or(ConversionError::MissingField("PrivateKey"))?,
+        public_key: peer
+            .get("publickey")
+            .cloned()
+            .ok_or(ConversionError::MissingPublicKey)?,
+        endpoint: peer
+            .get("endpoint")
+            .cloned()
+            .ok_or(ConversionError::MissingHost)?,
+        dns: interface.get("dns").map(|dns| join_list(dns)),
+        mtu: interface
+            .get("mtu")
+            .map(|mtu| {
+                mtu.parse()
+                    .map_err(|_| ConversionError::ParseError(format!("invalid mtu: {}", mtu)))
+            })
+            .transpose()?,
+        ip: interface
+            .get("address")
+            .map(|address| join_list(address))
+            .ok_or(ConversionError::MissingIP)?,
+        pre_shared_key: peer.get("presharedkey").cloned(),
+        persistent_keepalive: peer
+            .get("persistentkeepalive")
+            .map(|keepalive| {
+                keepalive.parse().map_err(|_| {
+                    ConversionError::ParseError(format!(
+                        "invalid PersistentKeepalive: {}",
+                        keepalive
+                    ))
+                })
+            })
+            .transpose()?,
+        amnezia: AmneziaConfig::from_params(&interface)?,
+        remark: None,
+    })
+}
+
+// wg-quick allows list keys such as Address and DNS to repeat.
+fn append(section: &mut HashMap<String, String>, key: String, value: String) {
+    section
+        .entry(key)
+        .and_modify(|existing| {
+            existing.push(',');
+            existing.push_str(&value);
+        })
+        .or_insert(value);
+}
+
+fn join_list(list: &str) -> String {
+    list.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .collect::<Vec<_>>()
+        .join(",")
+}
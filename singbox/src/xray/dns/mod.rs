@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Dns {
+    #[serde(default)]
+    pub servers: Vec<DnsServer>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query_strategy: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+// Xray takes either a bare resolver address or an object that limits the
+// resolver to some domains.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum DnsServer {
+    Address(String),
+    Options(DnsServerOptions),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DnsServerOptions {
+    pub address: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub domains: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skip_fallback: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
@@ -0,0 +1,528 @@
+use crate::error::ConversionError;
+use crate::fields::{non_empty, parse_int, parse_port};
+use crate::protocol::tls::{RealityConfig, TlsConfig, UTlsConfig};
+use crate::protocol::transport::{self, TransportConfig};
+use crate::protocol::Protocol;
+use serde_json::Value;
+use std::collections::HashMap;
+
+// Outbound protocols that carry no proxy node and are skipped on import.
+const NON_PROXY_PROTOCOLS: [&str; 5] = ["freedom", "blackhole", "dns", "loopback", "direct"];
+
+// Reads an Xray client config, or a JSON array of them, and returns every
+// proxy outbound as a `Protocol`.
+pub fn parse_config(content: &str) -> Result<Vec<Protocol>, ConversionError> {
+    let config: Value = serde_json::from_str(content).map_err(|_| ConversionError::InvalidJson)?;
+
+    let configs = match &config {
+        Value::Array(configs) => configs.iter().collect(),
+        Value::Object(_) => vec![&config],
+        _ => return Err(ConversionError::InvalidJson),
+    };
+
+    let mut protocols = Vec::new();
+    for config in configs {
+        let outbounds = config["outbounds"]
+            .as_array()
+            .ok_or(ConversionError::MissingField("outbounds"))?;
+        let mut nodes = Vec::new();
+        for outbound in outbounds {
+            let protocol = outbound["protocol"].as_str().unwrap_or_default();
+            if NON_PROXY_PROTOCOLS.contains(&protocol) {
+                continue;
+            }
+            match parse_outbound(outbound) {
+                Ok(node) => nodes.push(node),
+                Err(ConversionError::UnsupportedProtocol(protocol)) => {
+                    log::warn!("Skipping unsupported Xray outbound protocol: {}", protocol);
+                }
+                Err(e) => {
+                    log::warn!(
+                        "Skipping Xray outbound {}: {}",
+                        non_empty(&outbound["tag"]).unwrap_or_default(),
+                        e
+                    );
+                }
+            }
+        }
+        // Subscription entries name the node in `remarks`; the tag is usually
+        // just "proxy". With several proxies the tags are the better names.
+        if let ([node], Some(remarks)) = (nodes.as_mut_slice(), non_empty(&config["remarks"])) {
+            node.set_remark(Some(remarks));
+        }
+        protocols.extend(nodes);
+    }
+    Ok(protocols)
+}
+
+pub fn parse_outbound(outbound: &Value) -> Result<Protocol, ConversionError> {
+    let settings = &outbound["settings"];
+    let stream = &outbound["streamSettings"];
+    let remark = non_empty(&outbound["tag"]);
+
+    match outbound["protocol"].as_str().unwrap_or_default() {
+        "vmess" => {
+            let (host, port, user) = parse_vnext(settings)?;
+            Ok(Protocol::Vmess {
+                uuid: non_empty(&user["id"]).ok_or(ConversionError::MissingUUID)?,
+                host,
+                port,
+                alter_id: user["alterId"].as_u64().unwrap_or(0).to_string(),
+                security: non_empty(&user["security"]).unwrap_or_else(|| "auto".to_string()),
+                packet_encoding: None,
+                transport: parse_transport(stream)?,
+                tls: parse_security(stream)?,
+                remark,
+            })
+        }
+        "vless" => {
+            let (host, port, user) = parse_vnext(settings)?;
+            Ok(Protocol::Vless {
+                uuid: non_empty(&user["id"]).ok_or(ConversionError::MissingUUID)?,
+                host,
+                port,
+                flow: non_empty(&user["flow"]),
+                packet_encoding: None,
+                transport: parse_transport(stream)?,
+                tls: parse_security(stream)?,
+                remark,
+            })
+        }
+        "trojan" => {
+            let (host, port, server) = parse_server(settings)?;
+            Ok(Protocol::Trojan {
+                password: non_empty(&server["password"]).ok_or(ConversionError::MissingPassword)?,
+                host,
+                port,
+                transport: parse_transport(stream)?,
+                tls: parse_security(stream)?,
+                remark,
+            })
+        }
+        "shadowsocks" => {
+            let (host, port, server) = parse_server(settings)?;
+            Ok(Protocol::Shadowsocks {
+                method: non_empty(&server["method"])
+                    .ok_or(ConversionError::MissingField("method"))?,
+                password: non_empty(&server["password"]).ok_or(ConversionError::MissingPassword)?,
+                host,
+                port,
+                plugin: None,
+                plugin_opts: None,
+                remark,
+            })
+        }
+        "wireguard" => {
+            let peer = &settings["peers"][0];
+            Ok(Protocol::Wireguard {
+                private_key: non_empty(&settings["secretKey"])
+                    .ok_or(ConversionError::MissingField("secretKey"))?,
+                public_key: non_empty(&peer["publicKey"])
+                    .ok_or(ConversionError::MissingPublicKey)?,
+                endpoint: non_empty(&peer["endpoint"]).ok_or(ConversionError::MissingHost)?,
+                dns: None,
+                mtu: parse_int(&settings["mtu"], "mtu")?,
+                ip: parse_addresses(&settings["address"]).ok_or(ConversionError::MissingIP)?,
+                pre_shared_key: non_empty(&peer["preSharedKey"]),
+                persistent_keepalive: parse_int(&peer["keepAlive"], "keepAlive")?,
+                amnezia: None,
+                remark,
+            })
+        }
+        protocol => Err(ConversionError::UnsupportedProtocol(protocol.to_string())),
+    }
+}
+
+// VMess and VLESS keep the server under `settings.vnext[0]` and the credentials
+// under its first user.
+fn parse_vnext(settings: &Value) -> Result<(String, u16, &Value), ConversionError> {
+    let vnext = &settings["vnext"][0];
+    Ok((
+        non_empty(&vnext["address"]).ok_or(ConversionError::MissingHost)?,
+        parse_port(&vnext["port"])?,
+        &vnext["users"][0],
+    ))
+}
+
+// Trojan and Shadowsocks keep everything under `settings.servers[0]`.
+fn parse_server(settings: &Value) -> Result<(String, u16, &Value), ConversionError> {
+    let server = &settings["servers"][0];
+    Ok((
+        non_empty(&server["address"]).ok_or(ConversionError::MissingHost)?,
+        parse_port(&server["port"])?,
+        server,
+    ))
+}
+
+fn parse_transport(stream: &Value) -> Result<TransportConfig, ConversionError> {
+    let network = stream["network"].as_str().unwrap_or("tcp").to_lowercase();
+
+    match network.as_str() {
+        "tcp" | "raw" => {
+            let settings = if stream["rawSettings"].is_object() {
+                &stream["rawSettings"]
+            } else {
+                &stream["tcpSettings"]
+            };
+            let header = &settings["header"];
+            if header["type"].as_str() != Some("http") {
+                return Ok(TransportConfig::Tcp);
+            }
+
+            let request = &header["request"];
+            let mut headers = parse_headers(&request["headers"]);
+            let host = request["headers"]["Host"]
+                .as_array()
+                .map(|hosts| hosts.iter().filter_map(non_empty).collect())
+                .unwrap_or_else(|| headers.get("Host").cloned().into_iter().collect());
+            headers.remove("Host");
+
+            Ok(TransportConfig::Http {
+                host,
+                path: non_empty(&request["path"][0])
+                    .or_else(|| non_empty(&request["path"]))
+                    .unwrap_or_else(|| "/".to_string()),
+                method: non_empty(&request["method"]).unwrap_or_else(|| "GET".to_string()),
+                headers,
+                idle_timeout: Some("15s".to_string()),
+                ping_timeout: Some("15s".to_string()),
+            })
+        }
+        "ws" | "websocket" => {
+            let settings = &stream["wsSettings"];
+            let mut headers = parse_headers(&settings["headers"]);
+            if let Some(host) = non_empty(&settings["host"]) {
+                headers.insert("Host".to_string(), host);
+            }
+
+            // Xray encodes early data in the path as `?ed=2048`.
+            let path = non_empty(&settings["path"]).unwrap_or_default();
+            let (path, max_early_data) = match path.split_once("?ed=") {
+                Some((path, ed)) => (path.to_string(), ed.parse().unwrap_or(0)),
+                None => (path, 0),
+            };
+
+            Ok(TransportConfig::Websocket {
+                path,
+                headers,
+                max_early_data,
+                early_data_header_name: if max_early_data > 0 {
+                    "Sec-WebSocket-Protocol".to_string()
+                } else {
+                    String::new()
+                },
+            })
+        }
+        "grpc" | "gun" => {
+            let settings = &stream["grpcSettings"];
+            Ok(TransportConfig::Grpc {
+                service_name: non_empty(&settings["serviceName"]).unwrap_or_default(),
+                idle_timeout: Some("15s".to_string()),
+                ping_timeout: Some("15s".to_string()),
+                permit_without_stream: settings["permit_without_stream"].as_bool().unwrap_or(false),
+            })
+        }
+        "http" | "h2" => {
+            let settings = &stream["httpSettings"];
+            Ok(TransportConfig::Http {
+                host: settings["host"]
+                    .as_array()
+                    .map(|hosts| hosts.iter().filter_map(non_empty).collect())
+                    .unwrap_or_default(),
+                path: non_empty(&settings["path"]).unwrap_or_default(),
+                method: non_empty(&settings["method"]).unwrap_or_else(|| "GET".to_string()),
+                headers: parse_headers(&settings["headers"]),
+                idle_timeout: Some("15s".to_string()),
+                ping_timeout: Some("15s".to_string()),
+            })
+        }
+        "httpupgrade" => {
+            let settings = &stream["httpupgradeSettings"];
+            Ok(TransportConfig::Httpupgrade {
+                host: non_empty(&settings["host"]).unwrap_or_default(),
+                path: non_empty(&settings["path"]).unwrap_or_default(),
+                headers: parse_headers(&settings["headers"]),
+            })
+        }
+        "xhttp" | "splithttp" => {
+            let settings = if stream["xhttpSettings"].is_object() {
+                &stream["xhttpSettings"]
+            } else {
+                &stream["splithttpSettings"]
+            };
+            let mut path = non_empty(&settings["path"]).unwrap_or_default();
+            if !path.starts_with('/') {
+                path.insert(0, '/');
+            }
+
+            Ok(TransportConfig::Xhttp {
+                host: non_empty(&settings["host"]).unwrap_or_default(),
+                path,
+                mode: transport::check_xhttp_mode(
+                    non_empty(&settings["mode"]).unwrap_or_else(|| "auto".to_string()),
+                )?,
+                extra: settings.get("extra").filter(|e| e.is_object()).cloned(),
+            })
+        }
+        "kcp" | "mkcp" => {
+            let settings = &stream["kcpSettings"];
+            Ok(TransportConfig::Kcp {
+                seed: non_empty(&settings["seed"]),
+                header_type: transport::check_header_type(
+                    "mKCP",
+                    non_empty(&settings["header"]["type"]).unwrap_or_else(|| "none".to_string()),
+                )?,
+                mtu: parse_int(&settings["mtu"], "mtu")?,
+                tti: parse_int(&settings["tti"], "tti")?,
+                uplink_capacity: parse_int(&settings["uplinkCapacity"], "uplinkCapacity")?,
+                downlink_capacity: parse_int(&settings["downlinkCapacity"], "downlinkCapacity")?,
+                congestion: settings["congestion"].as_bool().unwrap_or(false),
+            })
+        }
+        "quic" => {
+            let settings = &stream["quicSettings"];
+            Ok(TransportConfig::Quic {
+                security: transport::check_quic_security(
+                    non_empty(&settings["security"]).unwrap_or_else(|| "none".to_string()),
+                )?,
+                key: non_empty(&settings["key"]).unwrap_or_default(),
+                header_type: transport::check_header_type(
+                    "QUIC",
+                    non_empty(&settings["header"]["type"]).unwrap_or_else(|| "none".to_string()),
+                )?,
+            })
+        }
+        _ => Err(ConversionError::InvalidTransportType(network)),
+    }
+}
+
+fn parse_security(stream: &Value) -> Result<TlsConfig, ConversionError> {
+    let security = stream["security"].as_str().unwrap_or("none");
+    let settings = match security {
+        "tls" => &stream["tlsSettings"],
+        "reality" => &stream["realitySettings"],
+        _ => return Ok(TlsConfig::default()),
+    };
+
+    let reality = if security == "reality" {
+        Some(RealityConfig {
+            public_key: non_empty(&settings["publicKey"]).ok_or(
+                ConversionError::MissingRealityParam("publicKey".to_string()),
+            )?,
+            short_id: non_empty(&settings["shortId"]).unwrap_or_default(),
+        })
+    } else {
+        None
+    };
+
+    Ok(TlsConfig {
+        enabled: true,
+        insecure: settings["allowInsecure"].as_bool().unwrap_or(false),
+        sni: non_empty(&settings["serverName"]),
+        alpn: settings["alpn"]
+            .as_array()
+            .map(|alpn| alpn.iter().filter_map(non_empty).collect())
+            .unwrap_or_default(),
+        utls: Some(UTlsConfig {
+            enabled: true,
+            fingerprint: non_empty(&settings["fingerprint"])
+                .unwrap_or_else(|| "chrome".to_string()),
+        }),
+        reality,
+    })
+}
+
+// A dual-stack interface lists one address per family; they are kept
+// comma-separated, as `split_addresses` reads them.
+fn parse_addresses(addresses: &Value) -> Option<String> {
+    let addresses: Vec<String> = match addresses {
+        Value::Array(addresses) => addresses.iter().filter_map(non_empty).collect(),
+        address => non_empty(address).into_iter().collect(),
+    };
+    Some(addresses.join(",")).filter(|a| !a.is_empty())
+}
+
+// Xray accepts header values as a string or a list; only the first value is kept.
+fn parse_headers(headers: &Value) -> HashMap<String, String> {
+    headers
+        .as_object()
+        .map(|headers| {
+            headers
+                .iter()
+                .filter_map(|(k, v)| {
+                    non_empty(v)
+                        .or_else(|| non_empty(&v[0]))
+                        .map(|v| (k.clone(), v))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn vless(tag: &str, port: Value) -> Value {
+        json!({
+            "protocol": "vless",
+            "tag": tag,
+            "settings": {
+                "vnext": [{ "address": "example.com", "port": port, "users": [{ "id": "uuid" }] }]
+            },
+            "streamSettings": { "network": "tcp" }
+        })
+    }
+
+    #[test]
+    fn unsupported_outbounds_are_skipped() {
+        let config = json!({
+            "outbounds": [
+                vless("proxy", json!(443)),
+                { "protocol": "socks", "tag": "socks", "settings": {} },
+                { "protocol": "freedom", "tag": "direct" }
+            ]
+        });
+        let protocols = parse_config(&config.to_string()).unwrap();
+        assert_eq!(protocols.len(), 1);
+    }
+
+    #[test]
+    fn remarks_only_name_a_single_proxy() {
+        let single = json!({ "remarks": "Node", "outbounds": [vless("proxy", json!(443))] });
+        let protocols = parse_config(&single.to_string()).unwrap();
+        assert_eq!(protocols[0].remark(), Some("Node"));
+
+        let several = json!({
+            "remarks": "Node",
+            "outbounds": [vless("a", json!(443)), vless("b", json!(8443))]
+        });
+        let protocols = parse_config(&several.to_string()).unwrap();
+        let remarks: Vec<_> = protocols.iter().map(Protocol::remark).collect();
+        assert_eq!(remarks, vec![Some("a"), Some("b")]);
+    }
+
+    #[test]
+    fn out_of_range_numbers_are_errors() {
+        assert!(parse_outbound(&vless("proxy", json!(70000))).is_err());
+        assert!(parse_outbound(&vless("proxy", json!("70000"))).is_err());
+
+        let wireguard = json!({
+            "protocol": "wireguard",
+            "settings": {
+                "secretKey": "key",
+                "address": ["10.0.0.2/32"],
+                "mtu": 70000,
+                "peers": [{ "publicKey": "pub", "endpoint": "example.com:51820" }]
+            }
+        });
+        assert!(parse_outbound(&wireguard).is_err());
+    }
+
+    #[test]
+    fn quic_and_kcp_options_are_checked() {
+        let mut outbound = vless("proxy", json!(443));
+        outbound["streamSettings"] = json!({
+            "network": "quic",
+            "quicSettings": { "security": "aes-128-gcm", "key": "k", "header": { "type": "srtp" } }
+        });
+        assert!(parse_outbound(&outbound).is_ok());
+        outbound["streamSettings"]["quicSettings"]["security"] = json!("rc4");
+        assert!(parse_outbound(&outbound).is_err());
+
+        outbound["streamSettings"] = json!({
+            "network": "kcp",
+            "kcpSettings": { "header": { "type": "http" } }
+        });
+        assert!(parse_outbound(&outbound).is_err());
+    }
+
+    #[test]
+    fn bad_outbounds_are_skipped() {
+        let config = json!({
+            "outbounds": [vless("bad", json!(70000)), vless("good", json!(443))]
+        });
+        let protocols = parse_config(&config.to_string()).unwrap();
+        assert_eq!(protocols.len(), 1);
+        assert_eq!(protocols[0].remark(), Some("good"));
+    }
+
+    #[test]
+    fn stream_settings_are_mapped() {
+        let mut outbound = vless("proxy", json!(443));
+        outbound["streamSettings"] = json!({
+            "network": "ws",
+            "wsSettings": { "host": "cdn.example.com", "path": "/ws?ed=2048" },
+            "security": "tls",
+            "tlsSettings": {
+                "serverName": "sni.example.com",
+                "alpn": ["h2", "http/1.1"],
+                "allowInsecure": true,
+                "fingerprint": "firefox"
+            }
+        });
+        let Protocol::Vless { transport, tls, .. } = parse_outbound(&outbound).unwrap() else {
+            panic!("expected VLESS");
+        };
+        assert_eq!(
+            transport,
+            TransportConfig::Websocket {
+                path: "/ws".to_string(),
+                headers: HashMap::from([("Host".to_string(), "cdn.example.com".to_string())]),
+                max_early_data: 2048,
+                early_data_header_name: "Sec-WebSocket-Protocol".to_string(),
+            }
+        );
+        assert!(tls.enabled && tls.insecure);
+        assert_eq!(tls.sni.as_deref(), Some("sni.example.com"));
+        assert_eq!(tls.alpn, vec!["h2", "http/1.1"]);
+        assert_eq!(tls.fingerprint(), Some("firefox"));
+        assert_eq!(tls.reality, None);
+
+        outbound["streamSettings"] = json!({
+            "network": "grpc",
+            "grpcSettings": { "serviceName": "tunnel" },
+            "security": "reality",
+            "realitySettings": { "serverName": "www.example.com", "publicKey": "pbk", "shortId": "ab12" }
+        });
+        let Protocol::Vless { transport, tls, .. } = parse_outbound(&outbound).unwrap() else {
+            panic!("expected VLESS");
+        };
+        let TransportConfig::Grpc { service_name, .. } = transport else {
+            panic!("expected gRPC, got {:?}", transport);
+        };
+        assert_eq!(service_name, "tunnel");
+        assert_eq!(
+            tls.reality,
+            Some(RealityConfig {
+                public_key: "pbk".to_string(),
+                short_id: "ab12".to_string(),
+            })
+        );
+        assert_eq!(tls.sni.as_deref(), Some("www.example.com"));
+        assert_eq!(tls.fingerprint(), Some("chrome"));
+
+        outbound["streamSettings"]["realitySettings"]["publicKey"] = json!("");
+        assert!(matches!(
+            parse_outbound(&outbound),
+            Err(ConversionError::MissingRealityParam(_))
+        ));
+    }
+
+    #[test]
+    fn every_wireguard_address_is_kept() {
+        let wireguard = json!({
+            "protocol": "wireguard",
+            "settings": {
+                "secretKey": "key",
+                "address": ["10.0.0.2/32", "fd00::2/128"],
+                "peers": [{ "publicKey": "pub", "endpoint": "example.com:51820" }]
+            }
+        });
+        let Protocol::Wireguard { ip, .. } = parse_outbound(&wireguard).unwrap() else {
+            panic!("expected WireGuard");
+        };
+        assert_eq!(ip, "10.0.0.2/32,fd00::2/128");
+    }
+}
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Inbound {
+    pub tag: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub listen: Option<String>,
+    pub port: u16,
+    pub protocol: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings: Option<InboundSettings>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sniffing: Option<Sniffing>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+// Settings of the socks and http inbounds; other protocols keep theirs in `extra`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InboundSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub udp: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sniffing {
+    pub enabled: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dest_override: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub route_only: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
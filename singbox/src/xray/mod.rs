@@ -0,0 +1,427 @@
+pub mod dns;
+pub mod import;
+pub mod inbound;
+pub mod outbound;
+pub mod routing;
+use crate::error::ConversionError;
+use crate::protocol::{with_unique_names, Protocol};
+use dns::{Dns, DnsServer, DnsServerOptions};
+use inbound::{Inbound, InboundSettings, Sniffing};
+use routing::{Routing, RoutingRule};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::fs;
+use std::path::Path;
+
+// Tag and protocol of the outbounds the routing preset sends traffic to.
+const BUILTIN_OUTBOUNDS: [(&str, &str); 3] = [
+    ("direct", "freedom"),
+    ("block", "blackhole"),
+    ("dns-out", "dns"),
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct XrayConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    remarks: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    log: Option<Log>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dns: Option<Dns>,
+    inbounds: Vec<Inbound>,
+    // Built by `Protocol::to_xray_outbound`, which owns each protocol's settings.
+    outbounds: Vec<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    routing: Option<Routing>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Log {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loglevel: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl XrayConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn remarks(&self) -> Option<&str> {
+        self.remarks.as_deref()
+    }
+
+    pub fn log(&self) -> Option<&Log> {
+        self.log.as_ref()
+    }
+
+    pub fn dns(&self) -> Option<&Dns> {
+        self.dns.as_ref()
+    }
+
+    pub fn inbounds(&self) -> &[Inbound] {
+        &self.inbounds
+    }
+
+    pub fn outbounds(&self) -> &[Value] {
+        &self.outbounds
+    }
+
+    pub fn routing(&self) -> Option<&Routing> {
+        self.routing.as_ref()
+    }
+
+    pub fn with_remarks(mut self, remarks: &str) -> Self {
+        self.remarks = Some(remarks.to_string());
+        self
+    }
+
+    pub fn with_inbound(mut self, inbound: Inbound) -> Self {
+        self.inbounds.push(inbound);
+        self
+    }
+
+    pub fn with_outbound(mut self, outbound: Value) -> Self {
+        self.outbounds.push(outbound);
+        self
+    }
+
+    pub fn with_dns(mut self, dns: Dns) -> Self {
+        self.dns = Some(dns);
+        self
+    }
+
+    pub fn with_routing(mut self, routing: Routing) -> Self {
+        self.routing = Some(routing);
+        self
+    }
+
+    // Xray log levels: debug, info, warning, error, none.
+    pub fn set_log_level(&mut self, level: &str) -> &mut Self {
+        self.log = Some(Log {
+            loglevel: Some(level.to_string()),
+            ..Default::default()
+        });
+        self
+    }
+
+    pub fn add_socks_inbound(&mut self) -> &mut Self {
+        self.inbounds.push(Inbound {
+            tag: "socks-in".to_string(),
+            listen: Some("127.0.0.1".to_string()),
+            port: 10808,
+            protocol: "socks".to_string(),
+            settings: Some(InboundSettings {
+                auth: Some("noauth".to_string()),
+                udp: Some(true),
+                ..Default::default()
+            }),
+            sniffing: Some(sniffing()),
+            ..Default::default()
+        });
+        self
+    }
+
+    pub fn add_http_inbound(&mut self) -> &mut Self {
+        self.inbounds.push(Inbound {
+            tag: "http-in".to_string(),
+            listen: Some("127.0.0.1".to_string()),
+            port: 10809,
+            protocol: "http".to_string(),
+            sniffing: Some(sniffing()),
+            ..Default::default()
+        });
+        self
+    }
+
+    // Proxy outbounds go ahead of the direct, block and DNS outbounds the
+    // routing preset refers to, so the first proxy is Xray's default route.
+    // Xray refuses duplicate tags: later proxies are tagged `proxy-2`, `proxy-3`
+    // and so on, and the built-in outbounds are only added once.
+    pub fn add_outbound(&mut self, protocol: Protocol) -> Result<(), ConversionError> {
+        protocol.validate()?;
+        let mut outbound = protocol.to_xray_outbound()?;
+
+        let proxies = self
+            .outbounds
+            .iter()
+            .position(|o| BUILTIN_OUTBOUNDS.iter().any(|(tag, _)| o["tag"] == *tag))
+            .unwrap_or(self.outbounds.len());
+        if proxies > 0 {
+            outbound["tag"] = json!(format!("proxy-{}", proxies + 1));
+        }
+        self.outbounds.insert(proxies, outbound);
+
+        for (tag, protocol) in BUILTIN_OUTBOUNDS {
+            if !self.outbounds.iter().any(|o| o["tag"] == tag) {
+                self.outbounds
+                    .push(json!({ "tag": tag, "protocol": protocol }));
+            }
+        }
+        Ok(())
+    }
+
+    // Same resolvers as the sing-box preset: AdGuard through the proxy, and
+    // Cloudflare directly for Iranian domains and the hosts needed at startup.
+    pub fn add_dns_server(&mut self) -> &mut Self {
+        self.dns = Some(Dns {
+            servers: vec![
+                DnsServer::Address("https://dns.adguard-dns.com/dns-query".to_string()),
+                DnsServer::Options(DnsServerOptions {
+                    address: "1.1.1.1".to_string(),
+                    domains: strings(&[
+                        "geosite:category-ir",
+                        "full:raw.githubusercontent.com",
+                        "full:time.apple.com",
+                    ]),
+                    skip_fallback: Some(true),
+                    ..Default::default()
+                }),
+            ],
+            query_strategy: Some("UseIP".to_string()),
+            tag: Some("dns-in".to_string()),
+            ..Default::default()
+        });
+        self
+    }
+
+    // Mirrors `SingBoxConfig::set_route`: DNS hijack, private and Iranian
+    // destinations direct, ads, trackers and QUIC blocked, everything else
+    // proxied. Blocking QUIC makes browsers fall back to TCP through the proxy.
+    pub fn set_route(&mut self) -> &mut Self {
+        let local_inbounds = || Some(strings(&["socks-in", "http-in"]));
+        self.routing = Some(Routing {
+            domain_strategy: Some("IPIfNonMatch".to_string()),
+            rules: vec![
+                RoutingRule {
+                    inbound_tag: local_inbounds(),
+                    port: Some("53".to_string()),
+                    outbound_tag: Some("dns-out".to_string()),
+                    ..Default::default()
+                },
+                RoutingRule {
+                    ip: Some(strings(&["1.1.1.1"])),
+                    port: Some("53".to_string()),
+                    outbound_tag: Some("direct".to_string()),
+                    ..Default::default()
+                },
+                RoutingRule {
+                    inbound_tag: Some(strings(&["dns-in"])),
+                    outbound_tag: Some("proxy".to_string()),
+                    ..Default::default()
+                },
+                RoutingRule {
+                    ip: Some(strings(&["geoip:private"])),
+                    outbound_tag: Some("direct".to_string()),
+                    ..Default::default()
+                },
+                RoutingRule {
+                    domain: Some(strings(&[
+                        "geosite:category-public-tracker",
+                        "geosite:category-ads",
+                        "geosite:category-ads-all",
+                        "geosite:google-ads",
+                    ])),
+                    outbound_tag: Some("block".to_string()),
+                    ..Default::default()
+                },
+                RoutingRule {
+                    domain: Some(strings(&["geosite:category-ir"])),
+                    outbound_tag: Some("direct".to_string()),
+                    ..Default::default()
+                },
+                RoutingRule {
+                    ip: Some(strings(&["geoip:ir"])),
+                    outbound_tag: Some("direct".to_string()),
+                    ..Default::default()
+                },
+                RoutingRule {
+                    network: Some("udp".to_string()),
+                    port: Some("443".to_string()),
+                    protocol: Some(strings(&["quic"])),
+                    outbound_tag: Some("block".to_string()),
+                    ..Default::default()
+                },
+                RoutingRule {
+                    inbound_tag: local_inbounds(),
+                    outbound_tag: Some("proxy".to_string()),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        });
+        self
+    }
+
+    // Builds an Xray JSON subscription as read by v2rayNG and Hiddify: one full
+    // config per node, each reusing this config's log, DNS, inbounds and routing
+    // and named like the other outputs name nodes. Nodes Xray cannot carry are
+    // skipped.
+    pub fn to_subscription(&self, protocols: &[Protocol]) -> Vec<XrayConfig> {
+        let mut configs = Vec::new();
+        for protocol in with_unique_names(protocols) {
+            let name = protocol.display_name();
+            let mut config = Self {
+                remarks: Some(name.clone()),
+                outbounds: Vec::new(),
+                ..self.clone()
+            };
+            match config.add_outbound(protocol) {
+                Ok(()) => configs.push(config),
+                Err(e) => log::warn!("Skipping {} in Xray subscription: {}", name, e),
+            }
+        }
+        configs
+    }
+
+    pub fn to_subscription_json(&self, protocols: &[Protocol]) -> Result<String, ConversionError> {
+        serde_json::to_string_pretty(&self.to_subscription(protocols))
+            .map_err(|e| ConversionError::SerializationError(e.to_string()))
+    }
+
+    pub fn to_json(&self) -> Result<String, ConversionError> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| ConversionError::SerializationError(e.to_string()))
+    }
+
+    pub fn save_to_file(&self, filename: &str) -> Result<(), ConversionError> {
+        fs::write(Path::new(filename), self.to_json()?)
+            .map_err(|e| ConversionError::IoError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    pub fn save_subscription(
+        &self,
+        protocols: &[Protocol],
+        filename: &str,
+    ) -> Result<(), ConversionError> {
+        fs::write(Path::new(filename), self.to_subscription_json(protocols)?)
+            .map_err(|e| ConversionError::IoError(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+fn sniffing() -> Sniffing {
+    Sniffing {
+        enabled: true,
+        dest_override: strings(&["http", "tls", "quic"]),
+        route_only: Some(true),
+        ..Default::default()
+    }
+}
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trojan(name: &str) -> Protocol {
+        Protocol::Trojan {
+            password: "secret".to_string(),
+            host: "example.com".to_string(),
+            port: 443,
+            transport: crate::protocol::transport::TransportConfig::Tcp,
+            tls: crate::protocol::tls::TlsConfig {
+                enabled: true,
+                ..Default::default()
+            },
+            remark: Some(name.to_string()),
+        }
+    }
+
+    #[test]
+    fn outbound_tags_stay_unique() {
+        let mut config = XrayConfig::new();
+        config.add_outbound(trojan("a")).unwrap();
+        config.add_outbound(trojan("b")).unwrap();
+        let tags: Vec<_> = config
+            .outbounds()
+            .iter()
+            .map(|o| o["tag"].as_str().unwrap())
+            .collect();
+        assert_eq!(tags, vec!["proxy", "proxy-2", "direct", "block", "dns-out"]);
+    }
+
+    #[test]
+    fn quic_block_precedes_the_proxy_catch_all() {
+        let mut config = XrayConfig::new();
+        config.set_route();
+        let rules = &config.routing().unwrap().rules;
+        let catch_all = rules
+            .iter()
+            .position(|rule| {
+                rule.outbound_tag.as_deref() == Some("proxy")
+                    && rule.inbound_tag == Some(strings(&["socks-in", "http-in"]))
+            })
+            .unwrap();
+        let quic = rules
+            .iter()
+            .position(|rule| rule.protocol == Some(strings(&["quic"])))
+            .unwrap();
+        assert!(quic < catch_all);
+        assert_eq!(rules[quic].outbound_tag.as_deref(), Some("block"));
+    }
+
+    #[test]
+    fn typed_sections_serialize_in_xray_format() {
+        let mut config = XrayConfig::new();
+        config.set_log_level("warning");
+        config.add_dns_server();
+        config.add_socks_inbound();
+        let value = serde_json::to_value(&config).unwrap();
+        assert_eq!(value["log"], json!({ "loglevel": "warning" }));
+        assert_eq!(value["dns"]["queryStrategy"], "UseIP");
+        assert_eq!(value["dns"]["servers"][1]["skipFallback"], true);
+        assert_eq!(
+            value["inbounds"][0]["settings"],
+            json!({ "auth": "noauth", "udp": true })
+        );
+        assert_eq!(
+            value["inbounds"][0]["sniffing"]["destOverride"],
+            json!(["http", "tls", "quic"])
+        );
+        assert!(value.get("routing").is_none());
+    }
+
+    #[test]
+    fn subscription_has_one_uniquely_named_config_per_node() {
+        let mut base = XrayConfig::new();
+        base.set_log_level("warning")
+            .add_socks_inbound()
+            .set_route();
+        let mut unnamed = trojan("a");
+        unnamed.set_remark(None);
+        let ssr = Protocol::ShadowsocksR {
+            method: "aes-256-cfb".to_string(),
+            password: "secret".to_string(),
+            host: "example.com".to_string(),
+            port: 8388,
+            ssr_protocol: "origin".to_string(),
+            protocol_param: None,
+            obfs: "plain".to_string(),
+            obfs_param: None,
+            remark: None,
+        };
+
+        let configs = base.to_subscription(&[trojan("a"), trojan("a"), unnamed, ssr]);
+        let remarks: Vec<_> = configs.iter().map(|c| c.remarks().unwrap()).collect();
+        assert_eq!(remarks, vec!["a", "a 2", "example.com:443"]);
+        for config in &configs {
+            assert_eq!(config.outbounds().len(), 4);
+            assert_eq!(config.outbounds()[0]["tag"], "proxy");
+            assert_eq!(config.inbounds(), base.inbounds());
+            assert_eq!(config.routing(), base.routing());
+        }
+
+        let json: Value =
+            serde_json::from_str(&base.to_subscription_json(&[trojan("a")]).unwrap()).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 1);
+        assert_eq!(json[0]["remarks"], "a");
+    }
+}
@@ -0,0 +1,201 @@
+use crate::error::ConversionError;
+use crate::protocol::tls::TlsConfig;
+use crate::protocol::transport::TransportConfig;
+use crate::protocol::{amnezia, parse_alter_id, split_addresses, Protocol};
+use serde_json::{json, Value};
+
+impl Protocol {
+    pub fn to_xray_outbound(&self) -> Result<Value, ConversionError> {
+        let mut config = match self {
+            Self::Vmess {
+                uuid,
+                host,
+                port,
+                alter_id,
+                security,
+                transport,
+                tls,
+                ..
+            } => json!({
+                "protocol": "vmess",
+                "settings": {
+                    "vnext": [{
+                        "address": host,
+                        "port": port,
+                        "users": [{
+                            "id": uuid,
+                            "alterId": parse_alter_id(alter_id)?,
+                            "security": security,
+                        }]
+                    }]
+                },
+                "streamSettings": stream_settings(transport, tls),
+            }),
+            Self::Vless {
+                uuid,
+                host,
+                port,
+                flow,
+                transport,
+                tls,
+                ..
+            } => {
+                let mut user = json!({
+                    "id": uuid,
+                    "encryption": "none",
+                });
+                if let Some(flow) = flow {
+                    user["flow"] = json!(flow);
+                }
+                json!({
+                    "protocol": "vless",
+                    "settings": {
+                        "vnext": [{
+                            "address": host,
+                            "port": port,
+                            "users": [user]
+                        }]
+                    },
+                    "streamSettings": stream_settings(transport, tls),
+                })
+            }
+            Self::Trojan {
+                password,
+                host,
+                port,
+                transport,
+                tls,
+                ..
+            } => json!({
+                "protocol": "trojan",
+                "settings": {
+                    "servers": [{
+                        "address": host,
+                        "port": port,
+                        "password": password,
+                    }]
+                },
+                "streamSettings": stream_settings(transport, tls),
+            }),
+            Self::Shadowsocks {
+                method,
+                password,
+                host,
+                port,
+                plugin,
+                ..
+            } => {
+                if let Some(plugin) = plugin {
+                    return Err(ConversionError::UnsupportedFeature(format!(
+                        "Xray does not support Shadowsocks plugins ({})",
+                        plugin
+                    )));
+                }
+                json!({
+                    "protocol": "shadowsocks",
+                    "settings": {
+                        "servers": [{
+                            "address": host,
+                            "port": port,
+                            "method": method,
+                            "password": password,
+                        }]
+                    }
+                })
+            }
+            Self::Wireguard {
+                private_key,
+                public_key,
+                endpoint,
+                mtu,
+                ip,
+                pre_shared_key,
+                persistent_keepalive,
+                amnezia,
+                ..
+            } => {
+                if amnezia.is_some() {
+                    return Err(amnezia::unsupported("Xray"));
+                }
+                let mut config = json!({
+                    "protocol": "wireguard",
+                    "settings": {
+                        "secretKey": private_key,
+                        "address": split_addresses(ip),
+                        "peers": [{
+                            "publicKey": public_key,
+                            "endpoint": endpoint,
+                        }]
+                    }
+                });
+                if let Some(mtu) = mtu {
+                    config["settings"]["mtu"] = json!(mtu);
+                }
+                if let Some(pre_shared_key) = pre_shared_key {
+                    config["settings"]["peers"][0]["preSharedKey"] = json!(pre_shared_key);
+                }
+                if let Some(keepalive) = persistent_keepalive {
+                    config["settings"]["peers"][0]["keepAlive"] = json!(keepalive);
+                }
+                config
+            }
+            Self::Hysteria2 { .. } => {
+                return Err(ConversionError::UnsupportedFeature(
+                    "Xray has no Hysteria2 outbound".to_string(),
+                ))
+            }
+            Self::Tuic { .. } => {
+                return Err(ConversionError::UnsupportedFeature(
+                    "Xray has no TUIC outbound".to_string(),
+                ))
+            }
+            Self::ShadowsocksR { .. } => {
+                return Err(ConversionError::UnsupportedFeature(
+                    "Xray has no ShadowsocksR outbound".to_string(),
+                ))
+            }
+        };
+
+        config["tag"] = json!("proxy");
+        Ok(config)
+    }
+}
+
+fn stream_settings(transport: &TransportConfig, tls: &TlsConfig) -> Value {
+    let mut settings = transport.to_xray_config();
+    if let (Some(settings), Value::Object(security)) =
+        (settings.as_object_mut(), tls.to_xray_config())
+    {
+        settings.extend(security);
+    }
+    settings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vmess(alter_id: &str) -> Protocol {
+        Protocol::Vmess {
+            uuid: "uuid".to_string(),
+            host: "example.com".to_string(),
+            port: 443,
+            alter_id: alter_id.to_string(),
+            security: "auto".to_string(),
+            packet_encoding: None,
+            transport: TransportConfig::Tcp,
+            tls: TlsConfig::default(),
+            remark: None,
+        }
+    }
+
+    #[test]
+    fn vmess_alter_id_must_be_numeric() {
+        let outbound = vmess("4").to_xray_outbound().unwrap();
+        assert_eq!(outbound["settings"]["vnext"][0]["users"][0]["alterId"], 4);
+        assert!(matches!(
+            vmess("four").to_xray_outbound(),
+            Err(ConversionError::ParseError(_))
+        ));
+    }
+}
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Routing {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain_strategy: Option<String>,
+    #[serde(default)]
+    pub rules: Vec<RoutingRule>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoutingRule {
+    #[serde(rename = "type", default = "field")]
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inbound_tag: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outbound_tag: Option<String>,
+    // Match fields we do not model, kept so loaded rules survive a round trip.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Default for RoutingRule {
+    fn default() -> Self {
+        Self {
+            kind: field(),
+            inbound_tag: None,
+            network: None,
+            port: None,
+            protocol: None,
+            ip: None,
+            domain: None,
+            outbound_tag: None,
+            extra: Map::new(),
+        }
+    }
+}
+
+// Xray's only rule type.
+fn field() -> String {
+    "field".to_string()
+}